@@ -0,0 +1,832 @@
+//! Build-time CSS extraction (`build` feature).
+//!
+//! Scans a crate's sources for the style macros, runs the same scoping pipeline
+//! the macros use and writes every stylesheet into one content-hashed `.css` file.
+//! Scope hashes are computed exactly as the macros compute them, so the markup
+//! generated at compile time matches the extracted stylesheet.
+//!
+//! ```no_run
+//! // build.rs
+//! let sheet = dioxus_style::build::Extractor::new()
+//!     .out_dir("assets")
+//!     .strip_inline(true)
+//!     .extract()
+//!     .expect("failed to extract styles");
+//!
+//! println!("cargo:rustc-env=DIOXUS_STYLE_SHEET={}", sheet.file_name);
+//! ```
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use dioxus_style_core::{
+    generate_hash, load_sources, parse_and_scope_with, replace_interpolations, wrap_declarations,
+    CssError, CssSource, LoadError, ScopeOptions,
+};
+
+/// Environment variable that makes the style macros leave the CSS out of the
+/// binary, set by [`Extractor::strip_inline`].
+pub const EXTRACTED_ENV: &str = "DIOXUS_STYLE_EXTRACTED";
+
+/// Error returned by [`Extractor::extract`].
+#[derive(Debug)]
+pub enum BuildError {
+    /// A source file, CSS file or output file could not be read or written.
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// A CSS file referenced by a macro was not found.
+    MissingFile { path: String, tried: Vec<PathBuf> },
+    /// A Sass file failed to compile.
+    Sass { path: String, message: String },
+    /// A stylesheet is not valid CSS.
+    Css { source: String, error: CssError },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            BuildError::MissingFile { path, tried } => {
+                write!(f, "failed to find CSS file '{}'. Tried paths:", path)?;
+                for candidate in tried {
+                    write!(f, "\n  - {}", candidate.display())?;
+                }
+                Ok(())
+            }
+            BuildError::Sass { path, message } => {
+                write!(f, "failed to compile '{}': {}", path, message)
+            }
+            BuildError::Css { source, error } => {
+                write!(f, "invalid CSS in {}", error.with_source(source))
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BuildError::Io { source, .. } => Some(source),
+            BuildError::Css { error, .. } => Some(error),
+            BuildError::MissingFile { .. } | BuildError::Sass { .. } => None,
+        }
+    }
+}
+
+/// The stylesheet written by [`Extractor::extract`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stylesheet {
+    /// Full path of the written file.
+    pub path: PathBuf,
+    /// File name, e.g. `dioxus_style-3kTMd2x.css`, for the `<link>` href.
+    pub file_name: String,
+    /// Scope ids included in the stylesheet, in source order.
+    pub scopes: Vec<String>,
+}
+
+/// Extracts the CSS of every style macro in a crate into a single file.
+#[derive(Debug, Clone)]
+pub struct Extractor {
+    manifest_dir: PathBuf,
+    src_dir: Option<PathBuf>,
+    out_dir: Option<PathBuf>,
+    minify: bool,
+    native_nesting: bool,
+    scoped_custom_properties: bool,
+    strip_inline: bool,
+}
+
+impl Default for Extractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Extractor {
+    /// Creates an extractor for the crate whose build script is running.
+    ///
+    /// Sources are read from `src/`, the file is written to `OUT_DIR`, and CSS is
+    /// minified for release builds, as the macros do.
+    pub fn new() -> Self {
+        let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR")
+            .map(PathBuf::from)
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_default();
+
+        Self {
+            manifest_dir,
+            src_dir: None,
+            out_dir: None,
+            minify: std::env::var("PROFILE").is_ok_and(|profile| profile == "release"),
+            native_nesting: cfg!(feature = "native-nesting"),
+            scoped_custom_properties: cfg!(feature = "scoped-custom-properties"),
+            strip_inline: false,
+        }
+    }
+
+    /// Sets the crate root CSS file paths are resolved against.
+    pub fn manifest_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.manifest_dir = dir.into();
+        self
+    }
+
+    /// Sets the directory scanned for Rust sources (default: `src/`).
+    pub fn src_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.src_dir = Some(dir.into());
+        self
+    }
+
+    /// Sets the directory the stylesheet is written to (default: `OUT_DIR`).
+    /// Relative paths are anchored at the crate root.
+    pub fn out_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.out_dir = Some(dir.into());
+        self
+    }
+
+    /// Sets whether the extracted CSS is minified.
+    pub fn minify(mut self, minify: bool) -> Self {
+        self.minify = minify;
+        self
+    }
+
+    /// Sets whether nested rules are kept as native CSS nesting instead of being
+    /// flattened. Must match the `native-nesting` feature of the `dioxus_style`
+    /// dependency the macros are compiled with (the default when this crate is
+    /// built with the same features).
+    pub fn native_nesting(mut self, native: bool) -> Self {
+        self.native_nesting = native;
+        self
+    }
+
+    /// Sets whether custom properties declared in a stylesheet are renamed to
+    /// scoped names. Must match the `scoped-custom-properties` feature of the
+    /// `dioxus_style` dependency, like [`Extractor::native_nesting`].
+    pub fn scoped_custom_properties(mut self, scoped: bool) -> Self {
+        self.scoped_custom_properties = scoped;
+        self
+    }
+
+    /// Leaves the CSS out of the compiled binary, so styles are only served
+    /// from the extracted file.
+    ///
+    /// Class names and accessors are unchanged; components still render their
+    /// `<style data-dioxus-style="...">` tags, but leave them empty.
+    pub fn strip_inline(mut self, strip: bool) -> Self {
+        self.strip_inline = strip;
+        self
+    }
+
+    /// Scans the sources, writes the stylesheet and returns where it was written.
+    ///
+    /// Emits `cargo:rerun-if-changed` for the sources and every CSS file read.
+    pub fn extract(&self) -> Result<Stylesheet, BuildError> {
+        let src_dir = self
+            .src_dir
+            .clone()
+            .unwrap_or_else(|| self.manifest_dir.join("src"));
+
+        let mut sources = Vec::new();
+        collect_rust_files(&src_dir, &mut sources)?;
+        sources.sort();
+        println!("cargo:rerun-if-changed={}", src_dir.display());
+
+        let mut seen = HashSet::new();
+        let mut scopes = Vec::new();
+        let mut css = String::new();
+
+        for source in &sources {
+            let code = read(source)?;
+            for usage in find_style_macros(&code) {
+                let (scope, scoped) = self.process(&usage)?;
+                if seen.insert(scope.clone()) {
+                    css.push_str(scoped.trim_end());
+                    css.push('\n');
+                    scopes.push(scope);
+                }
+            }
+        }
+
+        let out_dir = match &self.out_dir {
+            Some(dir) => self.manifest_dir.join(dir),
+            None => std::env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| self.manifest_dir.join("target")),
+        };
+        std::fs::create_dir_all(&out_dir).map_err(|source| BuildError::Io {
+            path: out_dir.clone(),
+            source,
+        })?;
+
+        let file_name = format!(
+            "dioxus_style-{}.css",
+            generate_hash(&css, None).trim_start_matches("sc_")
+        );
+        let path = out_dir.join(&file_name);
+        std::fs::write(&path, &css).map_err(|source| BuildError::Io {
+            path: path.clone(),
+            source,
+        })?;
+
+        if self.strip_inline {
+            println!("cargo:rustc-env={}=1", EXTRACTED_ENV);
+        }
+
+        Ok(Stylesheet {
+            path,
+            file_name,
+            scopes,
+        })
+    }
+
+    /// Scopes one macro invocation the way the macro itself does.
+    fn process(&self, usage: &StyleUsage) -> Result<(String, String), BuildError> {
+        match usage {
+            StyleUsage::Stylesheet(sources) => {
+                let loaded =
+                    load_sources(sources, &self.manifest_dir).map_err(|err| match err {
+                        LoadError::MissingFile { path, tried, .. } => {
+                            BuildError::MissingFile { path, tried }
+                        }
+                        LoadError::Sass { path, message, .. } => BuildError::Sass { path, message },
+                        LoadError::Css { source, error, .. } => BuildError::Css { source, error },
+                    })?;
+                for file in &loaded.files {
+                    println!("cargo:rerun-if-changed={}", file.resolved.display());
+                    for import in &file.imports {
+                        println!("cargo:rerun-if-changed={}", import.display());
+                    }
+                }
+
+                let options = ScopeOptions {
+                    minify: self.minify,
+                    ..ScopeOptions::from_flags(self.native_nesting, self.scoped_custom_properties)
+                };
+                let merged_error = |error| BuildError::Css {
+                    source: "<merged>".to_string(),
+                    error,
+                };
+                let dynamic =
+                    replace_interpolations(&loaded.css, &loaded.scope).map_err(merged_error)?;
+                let scoped = parse_and_scope_with(&dynamic.css, &loaded.scope, &options)
+                    .map_err(merged_error)?;
+                Ok((loaded.scope, scoped.scoped))
+            }
+            StyleUsage::Declarations(css) => {
+                let scope = generate_hash(css, None);
+                let dynamic =
+                    replace_interpolations(css, &scope).map_err(|error| BuildError::Css {
+                        source: "<inline>".to_string(),
+                        error,
+                    })?;
+                let wrapped = wrap_declarations(&dynamic.css, &scope, self.minify);
+                Ok((scope, wrapped))
+            }
+        }
+    }
+}
+
+fn read(path: &Path) -> Result<String, BuildError> {
+    std::fs::read_to_string(path).map_err(|source| BuildError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn collect_rust_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), BuildError> {
+    let entries = std::fs::read_dir(dir).map_err(|source| BuildError::Io {
+        path: dir.to_path_buf(),
+        source,
+    })?;
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_rust_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// The string literals passed to one of the style macros.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum StyleUsage {
+    /// `scoped_style!`, `#[with_css]` or `component_with_css!`: file paths and
+    /// stylesheets merged into one scope.
+    Stylesheet(Vec<CssSource>),
+    /// `css!`: declarations for the scope class.
+    Declarations(String),
+}
+
+/// Lexical token of Rust source, just detailed enough to spot macro calls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    Punct(char),
+    /// The value of a string literal.
+    Str(String),
+}
+
+/// Finds the string literals passed to the style macros in Rust source code.
+fn find_style_macros(code: &str) -> Vec<StyleUsage> {
+    let tokens = tokenize(code);
+    let mut usages = Vec::new();
+
+    let ident =
+        |i: usize, name: &str| matches!(tokens.get(i), Some(Token::Ident(id)) if id == name);
+    let punct = |i: usize, ch: char| tokens.get(i) == Some(&Token::Punct(ch));
+    // Function-like macros accept `(...)`, `[...]` and `{...}`
+    let open = |i: usize| punct(i, '(') || punct(i, '[') || punct(i, '{');
+    let string = |i: usize| match tokens.get(i) {
+        Some(Token::Str(value)) => Some(value.clone()),
+        _ => None,
+    };
+
+    for i in 0..tokens.len() {
+        // `::` paths end in the macro name, so a preceding `.` means a method call
+        if i > 0 && punct(i - 1, '.') {
+            continue;
+        }
+
+        if ident(i, "scoped_style") && punct(i + 1, '!') && open(i + 2) {
+            let sources = style_sources(&tokens, i + 3);
+            if !sources.is_empty() {
+                usages.push(StyleUsage::Stylesheet(sources));
+            }
+        } else if ident(i, "css") && punct(i + 1, '!') && open(i + 2) {
+            if let Some(value) = string(i + 3) {
+                usages.push(StyleUsage::Declarations(value));
+            }
+        } else if ident(i, "with_css") && punct(i + 1, '(') {
+            let sources = style_sources(&tokens, i + 2);
+            if !sources.is_empty() {
+                usages.push(StyleUsage::Stylesheet(sources));
+            }
+        } else if ident(i, "component_with_css") && punct(i + 1, '!') {
+            // Options come before the function
+            let mut j = i + 3;
+            while j < tokens.len() && !ident(j, "fn") && !punct(j, '#') {
+                if ident(j, "css") && punct(j + 1, ':') {
+                    if let Some(value) = string(j + 2) {
+                        usages.push(StyleUsage::Stylesheet(vec![CssSource::detect(value)]));
+                    }
+                    break;
+                }
+                j += 1;
+            }
+        }
+    }
+
+    usages
+}
+
+/// Reads the stylesheet arguments of a macro whose argument list starts at
+/// `start`: bare literals, `file = "..."` and `inline = "..."`, up to the closing
+/// parenthesis. Other arguments are skipped.
+fn style_sources(tokens: &[Token], start: usize) -> Vec<CssSource> {
+    let mut sources = Vec::new();
+    let mut depth = 0;
+    let mut i = start;
+
+    while let Some(token) = tokens.get(i) {
+        match token {
+            Token::Punct('(' | '[' | '{') => depth += 1,
+            Token::Punct(')' | ']' | '}') if depth == 0 => break,
+            Token::Punct(')' | ']' | '}') => depth -= 1,
+            Token::Str(value) if depth == 0 => {
+                let key = match (i.checked_sub(2).map(|k| &tokens[k]), &tokens[i - 1]) {
+                    (Some(Token::Ident(key)), Token::Punct('=')) => Some(key.as_str()),
+                    _ => None,
+                };
+                match key {
+                    Some("file") => sources.push(CssSource::File(value.clone())),
+                    Some("inline") => sources.push(CssSource::Inline(value.clone())),
+                    Some(_) => {}
+                    None => sources.push(CssSource::detect(value.clone())),
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    sources
+}
+
+/// Splits Rust source into identifiers, punctuation and string literals,
+/// skipping whitespace, comments, numbers and character literals.
+fn tokenize(code: &str) -> Vec<Token> {
+    let chars: Vec<char> = code.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let ch = chars[i];
+
+        if ch.is_whitespace() {
+            i += 1;
+        } else if ch == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if ch == '/' && chars.get(i + 1) == Some(&'*') {
+            // Block comments nest in Rust
+            let mut depth = 0;
+            while i < chars.len() {
+                if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+                    depth += 1;
+                    i += 2;
+                } else if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+        } else if ch == '"' {
+            let (token, next) = cooked_string(&chars, i + 1);
+            tokens.push(token);
+            i = next;
+        } else if let Some((start, hashes)) = raw_string_start(&chars, i) {
+            let (token, next) = raw_string(&chars, start, hashes);
+            tokens.push(token);
+            i = next;
+        } else if ch == '\'' {
+            i = skip_char_or_lifetime(&chars, i);
+        } else if ch.is_alphanumeric() || ch == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            // Prefixed strings (b"", c"") are not CSS, but must still be skipped
+            if matches!(word.as_str(), "b" | "c") && chars.get(i) == Some(&'"') {
+                i = cooked_string(&chars, i + 1).1;
+            } else if !ch.is_ascii_digit() {
+                tokens.push(Token::Ident(word));
+            }
+        } else {
+            tokens.push(Token::Punct(ch));
+            i += 1;
+        }
+    }
+
+    tokens
+}
+
+/// Reads a `"..."` literal whose body starts at `start`, decoding escapes.
+fn cooked_string(chars: &[char], start: usize) -> (Token, usize) {
+    let mut value = String::new();
+    let mut i = start;
+
+    while i < chars.len() && chars[i] != '"' {
+        if chars[i] != '\\' {
+            value.push(chars[i]);
+            i += 1;
+            continue;
+        }
+
+        i += 1;
+        match chars.get(i) {
+            Some('n') => value.push('\n'),
+            Some('t') => value.push('\t'),
+            Some('r') => value.push('\r'),
+            Some('0') => value.push('\0'),
+            Some('x') => {
+                let hex: String = chars.iter().skip(i + 1).take(2).collect();
+                if let Ok(byte) = u8::from_str_radix(&hex, 16) {
+                    value.push(byte as char);
+                }
+                i += 2;
+            }
+            Some('u') => {
+                let digits: String = chars[i + 1..]
+                    .iter()
+                    .skip(1)
+                    .take_while(|&&c| c != '}')
+                    .collect();
+                if let Some(c) = u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                {
+                    value.push(c);
+                }
+                i += digits.len() + 2;
+            }
+            Some('\n') => {
+                // Line continuation skips the newline and leading whitespace
+                while chars.get(i + 1).is_some_and(|c| c.is_whitespace()) {
+                    i += 1;
+                }
+            }
+            Some(&c) => value.push(c),
+            None => break,
+        }
+        i += 1;
+    }
+
+    (Token::Str(value), i + 1)
+}
+
+/// If a raw string literal (`r"`, `r#"`, `br"`...) starts at `i`, returns the
+/// index its body starts at and the number of `#`s.
+fn raw_string_start(chars: &[char], i: usize) -> Option<(usize, usize)> {
+    let mut j = i;
+    if matches!(chars.get(j), Some('b' | 'c')) {
+        j += 1;
+    }
+    if chars.get(j) != Some(&'r') {
+        return None;
+    }
+
+    j += 1;
+    let hashes = chars[j..].iter().take_while(|&&c| c == '#').count();
+    if chars.get(j + hashes) != Some(&'"') {
+        return None;
+    }
+    Some((j + hashes + 1, hashes))
+}
+
+/// Reads a raw string literal whose body starts at `start`.
+fn raw_string(chars: &[char], start: usize, hashes: usize) -> (Token, usize) {
+    let mut i = start;
+    while i < chars.len() {
+        if chars[i] == '"'
+            && chars[i + 1..]
+                .iter()
+                .take(hashes)
+                .filter(|&&c| c == '#')
+                .count()
+                == hashes
+        {
+            break;
+        }
+        i += 1;
+    }
+
+    let value = chars[start..i.min(chars.len())].iter().collect();
+    (Token::Str(value), i + 1 + hashes)
+}
+
+/// Skips a character literal starting at `i`, or just the quote of a lifetime.
+fn skip_char_or_lifetime(chars: &[char], i: usize) -> usize {
+    if chars.get(i + 1) == Some(&'\\') {
+        let mut j = i + 2;
+        while j < chars.len() && chars[j] != '\'' {
+            j += 1;
+        }
+        j + 1
+    } else if chars.get(i + 2) == Some(&'\'') {
+        i + 3
+    } else {
+        i + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_crate(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("dioxus_style_build_{}", name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("src/styles")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_finds_style_macros() {
+        let code = r##"
+            // scoped_style!("commented.css")
+            /* css!("color: blue;") /* nested */ */
+            #[with_css("styles/button.css")]
+            fn Button<'a>(label: &'a str) -> Element { let c = 'x'; let q = '\''; todo!() }
+
+            let a = scoped_style!(".title { content: \"\\}\"; }");
+            let b = dioxus_style::css!(r#"color: red;"#);
+            let c = "scoped_style!(\"in a string\")";
+            component_with_css! {
+                css: "styles/card.css",
+                /// css: "docs.css"
+                fn Card() -> Element { todo!() }
+            }
+        "##;
+
+        assert_eq!(
+            find_style_macros(code),
+            vec![
+                StyleUsage::Stylesheet(vec![CssSource::File("styles/button.css".to_string())]),
+                StyleUsage::Stylesheet(vec![CssSource::detect(
+                    ".title { content: \"\\}\"; }".to_string()
+                )]),
+                StyleUsage::Declarations("color: red;".to_string()),
+                StyleUsage::Stylesheet(vec![CssSource::File("styles/card.css".to_string())]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_finds_macros_with_any_delimiter() {
+        let code = r#"
+            let a = scoped_style! { ".a { }" };
+            let b = scoped_style!["base.css", inline = ".b { }"];
+            let c = css! { "color: red;" };
+            let d = css!["margin: 0;"];
+        "#;
+
+        assert_eq!(
+            find_style_macros(code),
+            vec![
+                StyleUsage::Stylesheet(vec![CssSource::Inline(".a { }".to_string())]),
+                StyleUsage::Stylesheet(vec![
+                    CssSource::File("base.css".to_string()),
+                    CssSource::Inline(".b { }".to_string()),
+                ]),
+                StyleUsage::Declarations("color: red;".to_string()),
+                StyleUsage::Declarations("margin: 0;".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_finds_multiple_sources() {
+        let code = r#"
+            #[with_css("base.css", file = "theme", inline = "a/b { }", other = "x.css")]
+            fn A() -> Element { todo!() }
+            let b = scoped_style!("base.css", (".not { }"), ".b { }");
+        "#;
+
+        assert_eq!(
+            find_style_macros(code),
+            vec![
+                StyleUsage::Stylesheet(vec![
+                    CssSource::File("base.css".to_string()),
+                    CssSource::File("theme".to_string()),
+                    CssSource::Inline("a/b { }".to_string()),
+                ]),
+                StyleUsage::Stylesheet(vec![
+                    CssSource::File("base.css".to_string()),
+                    CssSource::Inline(".b { }".to_string()),
+                ]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scopes_match_macros() {
+        let css = crate::scoped_style!(".build { color: red; }");
+        let declarations = crate::css!("margin: 0;");
+
+        let usages =
+            find_style_macros(r#"scoped_style!(".build { color: red; }"); css!("margin: 0;");"#);
+        let extractor = Extractor::new().minify(cfg!(not(debug_assertions)));
+
+        let (scope, scoped) = extractor.process(&usages[0]).unwrap();
+        assert_eq!(scope, css.scope());
+        assert_eq!(crate::inject_scope_styles(&scope), scoped);
+
+        let (scope, scoped) = extractor.process(&usages[1]).unwrap();
+        assert_eq!(scope, declarations.scope());
+        assert_eq!(crate::inject_scope_styles(&scope), scoped);
+
+        let merged = crate::scoped_style!(".one { color: red; }", inline = ".two { margin: 0; }");
+        let usages = find_style_macros(
+            r#"scoped_style!(".one { color: red; }", inline = ".two { margin: 0; }");"#,
+        );
+        let (scope, scoped) = extractor.process(&usages[0]).unwrap();
+        assert_eq!(scope, merged.scope());
+        assert_eq!(crate::inject_scope_styles(&scope), scoped);
+
+        // Nested rules are flattened or kept the same way as in the macros
+        let nested = crate::scoped_style!(".outer { .inner { color: red; } }");
+        let usages = find_style_macros(r#"scoped_style!(".outer { .inner { color: red; } }");"#);
+        let (scope, scoped) = extractor.process(&usages[0]).unwrap();
+        assert_eq!(scope, nested.scope());
+        assert_eq!(crate::inject_scope_styles(&scope), scoped);
+
+        // Custom properties are scoped the same way as in the macros
+        let variables = crate::scoped_style!(".vars { --gap: 0; margin: var(--gap); }");
+        let usages =
+            find_style_macros(r#"scoped_style!(".vars { --gap: 0; margin: var(--gap); }");"#);
+        let (scope, scoped) = extractor.process(&usages[0]).unwrap();
+        assert_eq!(scope, variables.scope());
+        assert_eq!(crate::inject_scope_styles(&scope), scoped);
+        assert_eq!(
+            scoped.contains("--gap"),
+            cfg!(not(feature = "scoped-custom-properties"))
+        );
+
+        // Interpolations become the same custom properties as in the macros
+        let width = 120;
+        let dynamic = crate::scoped_style!(".bar { width: {width}px; }");
+        let usages = find_style_macros(r#"scoped_style!(".bar { width: {width}px; }");"#);
+        let (scope, scoped) = extractor.process(&usages[0]).unwrap();
+        assert_eq!(scope, dynamic.scope());
+        assert_eq!(crate::inject_scope_styles(&scope), scoped);
+        assert_eq!(dynamic.style_attr(), format!("--{}_0: 120px;", scope));
+
+        let color = "teal";
+        let dynamic = crate::css!("color: {color};");
+        let usages = find_style_macros(r#"css!("color: {color};");"#);
+        let (scope, scoped) = extractor.process(&usages[0]).unwrap();
+        assert_eq!(scope, dynamic.scope());
+        assert_eq!(crate::inject_scope_styles(&scope), scoped);
+        assert_eq!(dynamic.style_attr(), format!("--{}_0: teal;", scope));
+
+        // CSS files are scanned for interpolations too
+        let (percent, color) = (40, "red");
+        let dynamic = crate::scoped_style!("tests/styles/progress.css");
+        let usages = find_style_macros(r#"scoped_style!("tests/styles/progress.css");"#);
+        let (scope, scoped) = extractor.process(&usages[0]).unwrap();
+        assert_eq!(scope, dynamic.scope());
+        assert_eq!(crate::inject_scope_styles(&scope), scoped);
+        assert!(scoped.contains(&format!("var(--{}_0)", scope)));
+        assert_eq!(
+            dynamic.style_attr(),
+            format!("--{0}_0: 40%; --{0}_1: red;", scope)
+        );
+        assert_eq!(css.style_attr(), "");
+    }
+
+    #[test]
+    fn test_extracts_single_hashed_stylesheet() {
+        let dir = temp_crate("extract");
+        std::fs::write(dir.join("src/styles/button.css"), ".btn { color: red; }").unwrap();
+        std::fs::write(
+            dir.join("src/main.rs"),
+            r#"#[with_css("styles/button.css")] fn A() {} #[with_css("styles/button.css")] fn B() {}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("src/inline.rs"), r#"css!("padding: 0;");"#).unwrap();
+
+        let sheet = Extractor::new()
+            .manifest_dir(&dir)
+            .out_dir("dist")
+            .minify(false)
+            .extract()
+            .unwrap();
+
+        let file_scope = generate_hash(".btn { color: red; }", Some("styles/button.css"));
+        let inline_scope = generate_hash("padding: 0;", None);
+        assert_eq!(sheet.scopes, vec![inline_scope.clone(), file_scope.clone()]);
+        assert_eq!(sheet.path, dir.join("dist").join(&sheet.file_name));
+        assert!(sheet.file_name.starts_with("dioxus_style-"));
+
+        let css = std::fs::read_to_string(&sheet.path).unwrap();
+        assert_eq!(
+            css,
+            format!(
+                ".{0} {{ padding: 0; }}\n.{1}_btn {{ color: red; }}\n",
+                inline_scope, file_scope
+            )
+        );
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_extract_errors() {
+        let dir = temp_crate("errors");
+        std::fs::write(dir.join("src/main.rs"), r#"scoped_style!("missing.css");"#).unwrap();
+        let err = Extractor::new()
+            .manifest_dir(&dir)
+            .out_dir("dist")
+            .extract();
+        assert!(matches!(err, Err(BuildError::MissingFile { .. })));
+
+        std::fs::write(
+            dir.join("src/main.rs"),
+            r#"scoped_style!(".a { color: red;");"#,
+        )
+        .unwrap();
+        let err = Extractor::new()
+            .manifest_dir(&dir)
+            .out_dir("dist")
+            .extract()
+            .unwrap_err();
+        assert!(err.to_string().starts_with("invalid CSS in <inline>:1:"));
+
+        // Fails to compile with `scss`, and can't be compiled without it
+        std::fs::write(dir.join("src/broken.scss"), ".a { color: $missing; }").unwrap();
+        std::fs::write(dir.join("src/main.rs"), r#"scoped_style!("broken.scss");"#).unwrap();
+        let err = Extractor::new()
+            .manifest_dir(&dir)
+            .out_dir("dist")
+            .extract()
+            .unwrap_err();
+        assert!(matches!(err, BuildError::Sass { .. }));
+        assert!(err
+            .to_string()
+            .starts_with("failed to compile 'broken.scss': "));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
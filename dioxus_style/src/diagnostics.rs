@@ -0,0 +1,30 @@
+//! Runtime diagnostics, routed through the `log` or `tracing` feature.
+//!
+//! Without either feature, debug messages are dropped. Warnings (e.g. a hot
+//! reload skipped because of invalid CSS) fall back to stderr, since they only
+//! come from development-time features.
+
+use std::fmt;
+
+/// Logs a debug message.
+#[allow(unused_variables)]
+pub(crate) fn debug(message: fmt::Arguments<'_>) {
+    #[cfg(feature = "log")]
+    log::debug!(target: "dioxus_style", "{}", message);
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(target: "dioxus_style", "{}", message);
+}
+
+/// Logs a warning.
+#[cfg_attr(not(feature = "hot-reload"), allow(dead_code))]
+pub(crate) fn warn(message: fmt::Arguments<'_>) {
+    #[cfg(feature = "log")]
+    log::warn!(target: "dioxus_style", "{}", message);
+
+    #[cfg(feature = "tracing")]
+    tracing::warn!(target: "dioxus_style", "{}", message);
+
+    #[cfg(not(any(feature = "log", feature = "tracing")))]
+    eprintln!("[dioxus_style] {}", message);
+}
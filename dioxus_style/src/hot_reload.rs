@@ -0,0 +1,406 @@
+//! CSS hot reloading (`hot-reload` feature).
+//!
+//! Polls the source files of file-based styles and re-scopes them into the
+//! global registry when they change, so CSS edits show up without recompiling.
+//! Styles merged from several files are rebuilt when any of them changes, and
+//! Sass files are recompiled when they or their partials change.
+
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Once};
+use std::time::{Duration, SystemTime};
+
+use dioxus_style_core::{
+    check_syntax, compile_sass, is_sass_path, parse_and_scope_with, replace_interpolations,
+    ScopeOptions,
+};
+
+use crate::diagnostics;
+use crate::runtime_injector::{StyleSource, STYLE_REGISTRY};
+
+/// How often watched files are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+type ReloadListener = Box<dyn Fn(&str) + Send + Sync>;
+
+lazy_static! {
+    /// Watched CSS files, keyed by absolute path.
+    static ref WATCHED: Mutex<HashMap<PathBuf, WatchedFile>> = Mutex::new(HashMap::new());
+    /// The stylesheets each watched scope is merged from, in order.
+    static ref SCOPE_SOURCES: Mutex<HashMap<String, Vec<Source>>> = Mutex::new(HashMap::new());
+    /// The latest CSS of every reloaded scope, which replaces the compiled-in CSS
+    /// when a scope is registered again.
+    static ref RELOADED: Mutex<HashMap<String, Arc<str>>> = Mutex::new(HashMap::new());
+    /// Callbacks invoked with the scope of every reloaded style.
+    static ref LISTENERS: Mutex<Vec<ReloadListener>> = Mutex::new(Vec::new());
+}
+
+static START_WATCHER: Once = Once::new();
+
+/// A CSS file backing one or more registered scopes.
+struct WatchedFile {
+    scopes: Vec<String>,
+    modified: Option<SystemTime>,
+}
+
+/// An owned [`StyleSource`].
+enum Source {
+    File(PathBuf),
+    Inline(String),
+}
+
+/// Starts watching the files in `sources` and re-scoping them into `scope`
+/// when any of them changes.
+pub(crate) fn watch(scope: &str, sources: &[StyleSource<'_>]) {
+    {
+        let mut scope_sources = SCOPE_SOURCES
+            .lock()
+            .expect("hot reload watch list lock poisoned");
+        if scope_sources.contains_key(scope) {
+            return;
+        }
+        scope_sources.insert(
+            scope.to_string(),
+            sources
+                .iter()
+                .map(|source| match source {
+                    StyleSource::File(path) => Source::File(PathBuf::from(path)),
+                    StyleSource::Inline(css) => Source::Inline(css.to_string()),
+                })
+                .collect(),
+        );
+    }
+
+    for source in sources {
+        let StyleSource::File(path) = source else {
+            continue;
+        };
+        let path = Path::new(path);
+        watch_file(path, scope);
+
+        // Sass partials are watched too
+        if is_sass(path) {
+            if let Some((_, imports)) = read_file(path) {
+                for import in imports {
+                    watch_file(&import, scope);
+                }
+            }
+        }
+    }
+
+    START_WATCHER.call_once(|| {
+        std::thread::Builder::new()
+            .name("dioxus_style-hot-reload".to_string())
+            .spawn(|| loop {
+                std::thread::sleep(POLL_INTERVAL);
+                check_for_changes();
+            })
+            .expect("failed to spawn hot reload thread");
+    });
+}
+
+/// Adds `scope` to the scopes rebuilt when the file at `path` changes.
+fn watch_file(path: &Path, scope: &str) {
+    let mut watched = WATCHED.lock().expect("hot reload watch list lock poisoned");
+    let file = watched
+        .entry(path.to_path_buf())
+        .or_insert_with(|| WatchedFile {
+            scopes: Vec::new(),
+            modified: modified_time(path),
+        });
+    if !file.scopes.iter().any(|watched| watched == scope) {
+        file.scopes.push(scope.to_string());
+    }
+}
+
+/// Registers a callback invoked with the scope of each style that was reloaded.
+///
+/// Components injecting their own styles are re-rendered automatically. Use this
+/// when the styles are rendered elsewhere, e.g. from `inject_styles()` in a root
+/// component, to re-render that part of the app.
+pub fn on_reload(listener: impl Fn(&str) + Send + Sync + 'static) {
+    LISTENERS
+        .lock()
+        .expect("hot reload listener lock poisoned")
+        .push(Box::new(listener));
+}
+
+/// Reloads every scope backed by a file whose modification time changed.
+fn check_for_changes() {
+    let mut changed: Vec<String> = Vec::new();
+    {
+        let mut watched = WATCHED.lock().expect("hot reload watch list lock poisoned");
+        for (path, file) in watched.iter_mut() {
+            let modified = modified_time(path);
+            if modified == file.modified {
+                continue;
+            }
+            file.modified = modified;
+            for scope in &file.scopes {
+                if !changed.contains(scope) {
+                    changed.push(scope.clone());
+                }
+            }
+        }
+    }
+
+    for scope in changed {
+        reload(&scope);
+    }
+}
+
+/// Re-merges and re-scopes the stylesheets of `scope`, replaces its registry
+/// entry and re-renders the component instance rendering its `<style>` tag.
+///
+/// The scope is kept, so class names used in markup stay valid.
+fn reload(scope: &str) -> bool {
+    let mut imports = Vec::new();
+    let css = {
+        let scope_sources = SCOPE_SOURCES
+            .lock()
+            .expect("hot reload watch list lock poisoned");
+        let Some(sources) = scope_sources.get(scope) else {
+            return false;
+        };
+
+        let mut parts = Vec::with_capacity(sources.len());
+        for source in sources {
+            match source {
+                Source::File(path) => {
+                    let Some((css, file_imports)) = read_file(path) else {
+                        return false;
+                    };
+                    imports.extend(file_imports);
+                    if let Err(err) = check_syntax(&css) {
+                        diagnostics::warn(format_args!(
+                            "hot reload skipped: {}",
+                            err.with_source(&path.display().to_string())
+                        ));
+                        return false;
+                    }
+                    parts.push(css);
+                }
+                Source::Inline(css) => parts.push(css.clone()),
+            }
+        }
+        // Interpolations map to the same custom properties as at compile time
+        match replace_interpolations(&parts.join("\n"), scope) {
+            Ok(dynamic) => dynamic.css,
+            Err(err) => {
+                diagnostics::warn(format_args!(
+                    "hot reload skipped: {}",
+                    err.with_source("<merged>")
+                ));
+                return false;
+            }
+        }
+    };
+
+    let options = ScopeOptions::from_flags(
+        cfg!(feature = "native-nesting"),
+        cfg!(feature = "scoped-custom-properties"),
+    );
+    let scoped = match parse_and_scope_with(&css, scope, &options) {
+        Ok(scoped) => scoped,
+        Err(err) => {
+            diagnostics::warn(format_args!(
+                "hot reload skipped: {}",
+                err.with_source("<merged>")
+            ));
+            return false;
+        }
+    };
+
+    RELOADED
+        .lock()
+        .expect("hot reload CSS lock poisoned")
+        .insert(scope.to_string(), Arc::from(scoped.scoped.as_str()));
+    let rerender = {
+        let mut registry = STYLE_REGISTRY.lock().expect("StyleRegistry lock poisoned");
+        registry.register(scope.to_string(), scoped.scoped);
+        registry.injection_rerender(scope)
+    };
+    if let Some(rerender) = rerender {
+        rerender();
+    }
+
+    // Partials added since the last build
+    for import in &imports {
+        watch_file(import, scope);
+    }
+    diagnostics::debug(format_args!("reloaded styles for scope {}", scope));
+
+    for listener in LISTENERS
+        .lock()
+        .expect("hot reload listener lock poisoned")
+        .iter()
+    {
+        listener(scope);
+    }
+
+    true
+}
+
+/// Returns the last hot-reloaded CSS of `scope`, if it was reloaded.
+pub(crate) fn reloaded_css(scope: &str) -> Option<Arc<str>> {
+    RELOADED
+        .lock()
+        .expect("hot reload CSS lock poisoned")
+        .get(scope)
+        .cloned()
+}
+
+/// Reads a watched file, compiling Sass files. Returns the CSS and the partials
+/// it imports, or `None` if it can't be read or compiled right now.
+fn read_file(path: &Path) -> Option<(String, Vec<PathBuf>)> {
+    if is_sass(path) {
+        return match compile_sass(path) {
+            Ok(compiled) => Some((compiled.css, compiled.imports)),
+            Err(message) => {
+                diagnostics::warn(format_args!("hot reload skipped: {}", message));
+                None
+            }
+        };
+    }
+
+    // The file may be missing briefly while an editor saves it
+    let css = std::fs::read_to_string(path).ok()?;
+    Some((css, Vec::new()))
+}
+
+#[inline]
+fn is_sass(path: &Path) -> bool {
+    is_sass_path(&path.to_string_lossy())
+}
+
+#[inline]
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_reload_replaces_registry_entry() {
+        let path = std::env::temp_dir().join("dioxus_style_hot_reload_test.css");
+        std::fs::write(&path, ".button { color: red; }").unwrap();
+
+        let scope = "sc_hotreload";
+        crate::ScopedStyle::from_file(scope.to_string(), String::new(), path.to_str().unwrap());
+        assert!(WATCHED.lock().unwrap().contains_key(&path));
+
+        let reloads = Arc::new(AtomicUsize::new(0));
+        let counter = reloads.clone();
+        on_reload(move |reloaded| {
+            if reloaded == scope {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        std::fs::write(&path, ".button { color: blue; }").unwrap();
+        assert!(reload(scope));
+        assert!(crate::inject_styles().contains(".sc_hotreload_button { color: blue; }"));
+        assert_eq!(reloads.load(Ordering::SeqCst), 1);
+
+        // Invalid CSS keeps the previous styles
+        std::fs::write(&path, ".button { color: green;").unwrap();
+        assert!(!reload(scope));
+        assert!(crate::inject_styles().contains("color: blue;"));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_reload_rerenders_owner_and_updates_cached_css() {
+        let path = std::env::temp_dir().join("dioxus_style_hot_reload_owner.css");
+        std::fs::write(&path, ".owner { color: red; }").unwrap();
+
+        let scope = "sc_hotreload_owner";
+        let style = crate::ScopedStyle::from_file(
+            scope.to_string(),
+            ".sc_hotreload_owner_owner { color: red; }".to_string(),
+            path.to_str().unwrap(),
+        );
+        let rerenders = Arc::new(AtomicUsize::new(0));
+        let counter = rerenders.clone();
+        let owner = crate::StyleInjection::new(&style).rerender_with(Arc::new(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        }));
+        assert!(owner.styles().contains("color: red"));
+
+        std::fs::write(&path, ".owner { color: blue; }").unwrap();
+        assert!(reload(scope));
+        assert_eq!(rerenders.load(Ordering::SeqCst), 1);
+        assert!(owner.styles().contains("color: blue"));
+
+        // Registering the scope again uses the reloaded CSS, not the compiled-in one
+        let registry = Arc::new(Mutex::new(crate::StyleRegistry::new()));
+        crate::with_registry(registry.clone(), || style.instantiate());
+        let css = registry
+            .lock()
+            .unwrap()
+            .get_style(scope)
+            .unwrap()
+            .to_string();
+        assert!(css.contains("color: blue"));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_reload_merges_sources() {
+        let base = std::env::temp_dir().join("dioxus_style_hot_reload_base.css");
+        let button = std::env::temp_dir().join("dioxus_style_hot_reload_button.css");
+        std::fs::write(&base, ".base { margin: 0; }").unwrap();
+        std::fs::write(&button, ".button { color: red; }").unwrap();
+
+        let scope = "sc_hotreload_merged";
+        crate::ScopedStyle::from_sources(
+            scope.to_string(),
+            String::new(),
+            &[
+                StyleSource::File(base.to_str().unwrap()),
+                StyleSource::File(button.to_str().unwrap()),
+                StyleSource::Inline(".extra { padding: 0; }"),
+            ],
+        );
+        assert_eq!(WATCHED.lock().unwrap()[&button].scopes, vec![scope]);
+
+        std::fs::write(&button, ".button { color: blue; }").unwrap();
+        assert!(reload(scope));
+        let css = crate::inject_scope_styles(scope);
+        assert!(css.contains(".sc_hotreload_merged_base { margin: 0; }"));
+        assert!(css.contains(".sc_hotreload_merged_button { color: blue; }"));
+        assert!(css.contains(".sc_hotreload_merged_extra { padding: 0; }"));
+
+        std::fs::remove_file(&base).ok();
+        std::fs::remove_file(&button).ok();
+    }
+
+    #[cfg(feature = "scss")]
+    #[test]
+    fn test_reload_watches_sass_partials() {
+        let dir = std::env::temp_dir().join("dioxus_style_hot_reload_sass");
+        std::fs::create_dir_all(&dir).unwrap();
+        let theme = dir.join("_theme.scss");
+        let card = dir.join("card.scss");
+        std::fs::write(&theme, "$accent: red;").unwrap();
+        std::fs::write(&card, "@use 'theme';\n.card { color: theme.$accent; }").unwrap();
+
+        let scope = "sc_hotreload_sass";
+        crate::ScopedStyle::from_file(scope.to_string(), String::new(), card.to_str().unwrap());
+        assert_eq!(WATCHED.lock().unwrap()[&theme].scopes, vec![scope]);
+
+        std::fs::write(&theme, "$accent: blue;").unwrap();
+        assert!(reload(scope));
+        assert!(crate::inject_scope_styles(scope).contains(".sc_hotreload_sass_card {"));
+        assert!(crate::inject_scope_styles(scope).contains("color: blue;"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -1,7 +1,18 @@
 //! dioxus_style/src/lib.rs
 //! Scoped CSS styling for Dioxus
 
+// Lets the macros' `::dioxus_style` paths resolve inside this crate's tests
+#[cfg(test)]
+extern crate self as dioxus_style;
+
+#[cfg(feature = "build")]
+pub mod build;
+mod diagnostics;
+#[cfg(feature = "hot-reload")]
+mod hot_reload;
+mod registry_scope;
 mod runtime_injector;
+mod style_collector;
 
 // Re-export core macros
 pub use dioxus_style_macro::{
@@ -12,7 +23,16 @@ pub use dioxus_style_macro::{
 };
 
 // Export runtime components
-pub use runtime_injector::{inject_styles, ScopedStyle, StyleRegistry, STYLE_REGISTRY};
+pub use registry_scope::{with_registry, with_registry_async, WithRegistry};
+pub use runtime_injector::{
+    hydrate_scopes, inject_scope_styles, inject_styles, set_ref_counting, style_tag, ScopedStyle,
+    StyleInjection, StyleRegistry, StyleSource, HYDRATED_STYLE_SELECTOR, STYLE_REGISTRY,
+    STYLE_SCOPE_ATTRIBUTE,
+};
+pub use style_collector::{collect_styles, CollectedStyles, StyleCollection};
+
+#[cfg(feature = "hot-reload")]
+pub use hot_reload::on_reload;
 
 // Re-export lazy_static for internal use
 pub use lazy_static::lazy_static;
@@ -0,0 +1,204 @@
+//! Request-scoped style registries for concurrent server-side rendering.
+//!
+//! By default every style is registered in the process-wide [`STYLE_REGISTRY`].
+//! A render wrapped in [`with_registry`] (or a future wrapped in
+//! [`with_registry_async`]) uses its own registry instead, so concurrent requests
+//! keep separate style sets and don't contend on a single lock.
+//!
+//! [`STYLE_REGISTRY`]: crate::STYLE_REGISTRY
+
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use crate::runtime_injector::{StyleRegistry, STYLE_REGISTRY};
+
+thread_local! {
+    /// Registry installed for the render running on this thread, if any.
+    static LOCAL_REGISTRY: RefCell<Option<Arc<Mutex<StyleRegistry>>>> = const { RefCell::new(None) };
+}
+
+/// Returns the registry styles should be read from and registered into: the one
+/// installed on this thread, or the global registry.
+#[inline]
+pub(crate) fn active_registry() -> Arc<Mutex<StyleRegistry>> {
+    local_registry().unwrap_or_else(|| STYLE_REGISTRY.clone())
+}
+
+/// Returns the registry installed on this thread, if any.
+#[inline]
+pub(crate) fn local_registry() -> Option<Arc<Mutex<StyleRegistry>>> {
+    LOCAL_REGISTRY.with(|local| local.borrow().clone())
+}
+
+/// Restores the previously installed registry when dropped, even on panic.
+struct RegistryGuard {
+    previous: Option<Arc<Mutex<StyleRegistry>>>,
+}
+
+impl RegistryGuard {
+    fn install(registry: Arc<Mutex<StyleRegistry>>) -> Self {
+        let previous = LOCAL_REGISTRY.with(|local| local.borrow_mut().replace(registry));
+        Self { previous }
+    }
+}
+
+impl Drop for RegistryGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        LOCAL_REGISTRY.with(|local| *local.borrow_mut() = previous);
+    }
+}
+
+/// Runs `render` with `registry` in place of the global registry.
+///
+/// Styles used during the render are registered in `registry`, and
+/// [`inject_styles`](crate::inject_styles) and the per-component `<style>` tags
+/// read from it. Calls can be nested; the previous registry is restored afterwards.
+///
+/// ```
+/// use std::sync::{Arc, Mutex};
+/// use dioxus_style::{inject_styles, scoped_style, with_registry, StyleRegistry};
+///
+/// let registry = Arc::new(Mutex::new(StyleRegistry::new()));
+/// let css = with_registry(registry.clone(), || {
+///     // Render here
+///     let css = scoped_style!(".card { padding: 1rem; }");
+///     assert!(inject_styles().contains(css.scope()));
+///     css
+/// });
+///
+/// assert!(registry.lock().unwrap().contains(css.scope()));
+/// ```
+pub fn with_registry<R>(registry: Arc<Mutex<StyleRegistry>>, render: impl FnOnce() -> R) -> R {
+    let _guard = RegistryGuard::install(registry);
+    render()
+}
+
+/// Wraps `future` so that every poll runs with `registry` in place of the global
+/// registry.
+///
+/// Use this for async renders on a multi-threaded runtime, where a task may be
+/// polled on a different thread each time.
+pub fn with_registry_async<F: Future>(
+    registry: Arc<Mutex<StyleRegistry>>,
+    future: F,
+) -> WithRegistry<F> {
+    WithRegistry {
+        registry,
+        future: Box::pin(future),
+    }
+}
+
+/// Future returned by [`with_registry_async`].
+#[must_use = "futures do nothing unless polled"]
+pub struct WithRegistry<F> {
+    registry: Arc<Mutex<StyleRegistry>>,
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for WithRegistry<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let _guard = RegistryGuard::install(self.registry.clone());
+        self.future.as_mut().poll(cx)
+    }
+}
+
+impl<F> std::fmt::Debug for WithRegistry<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WithRegistry")
+            .field("registry", &self.registry)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{inject_scope_styles, inject_styles, ScopedStyle, StyleInjection};
+    use std::task::{Wake, Waker};
+
+    fn new_registry() -> Arc<Mutex<StyleRegistry>> {
+        Arc::new(Mutex::new(StyleRegistry::new()))
+    }
+
+    #[test]
+    fn test_local_registry_is_isolated() {
+        let style = ScopedStyle::new("sc_local_shared".to_string(), "css_shared".to_string());
+        let first = new_registry();
+        let second = new_registry();
+
+        with_registry(first.clone(), || {
+            style.instantiate();
+            ScopedStyle::new("sc_local_only".to_string(), "css_only".to_string());
+            assert_eq!(inject_styles(), "css_shared\ncss_only\n");
+        });
+        with_registry(second.clone(), || {
+            assert!(inject_styles().is_empty());
+            let injection = StyleInjection::new(&style);
+            assert_eq!(injection.styles(), "css_shared");
+        });
+
+        assert_eq!(first.lock().unwrap().len(), 2);
+        assert_eq!(second.lock().unwrap().len(), 1);
+        assert_eq!(second.lock().unwrap().mount_count("sc_local_shared"), 0);
+
+        // The global registry still sees every style
+        assert_eq!(inject_scope_styles("sc_local_only"), "css_only");
+    }
+
+    #[test]
+    fn test_nested_registries_are_restored() {
+        let outer = new_registry();
+        let inner = new_registry();
+
+        with_registry(outer.clone(), || {
+            with_registry(inner.clone(), || {
+                assert!(Arc::ptr_eq(&active_registry(), &inner));
+            });
+            assert!(Arc::ptr_eq(&active_registry(), &outer));
+        });
+
+        assert!(local_registry().is_none());
+        assert!(Arc::ptr_eq(&active_registry(), &*STYLE_REGISTRY));
+    }
+
+    #[test]
+    fn test_registry_restored_after_panic() {
+        let result = std::panic::catch_unwind(|| {
+            with_registry(new_registry(), || panic!("render failed"));
+        });
+
+        assert!(result.is_err());
+        assert!(local_registry().is_none());
+    }
+
+    #[test]
+    fn test_async_registry_is_installed_per_poll() {
+        struct NoopWaker;
+
+        impl Wake for NoopWaker {
+            fn wake(self: Arc<Self>) {}
+        }
+
+        let registry = new_registry();
+        let mut future = with_registry_async(registry.clone(), async {
+            ScopedStyle::new("sc_local_async".to_string(), "css_async".to_string());
+            inject_styles()
+        });
+
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+
+        assert_eq!(
+            Pin::new(&mut future).poll(&mut cx),
+            Poll::Ready("css_async\n".to_string())
+        );
+        assert!(registry.lock().unwrap().contains("sc_local_async"));
+        assert!(local_registry().is_none());
+    }
+}
@@ -3,9 +3,22 @@
 //! Manages the collection and injection of scoped styles into the DOM.
 
 use lazy_static::lazy_static;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 
+use crate::diagnostics;
+use crate::registry_scope::{active_registry, local_registry};
+
+/// Attribute carrying the scope id on injected `<style>` tags.
+pub const STYLE_SCOPE_ATTRIBUTE: &str = "data-dioxus-style";
+
+/// CSS selector for the server-rendered `<style>` tags to pass to
+/// [`hydrate_scopes`]. Components render their own tags in the body, so only
+/// the ones in the `<head>` count.
+pub const HYDRATED_STYLE_SELECTOR: &str = "head > style[data-dioxus-style]";
+
 lazy_static! {
     /// Global registry for all scoped styles.
     pub static ref STYLE_REGISTRY: Arc<Mutex<StyleRegistry>> = Arc::new(Mutex::new(StyleRegistry::new()));
@@ -18,6 +31,16 @@ pub struct StyleRegistry {
     styles: HashMap<String, String>,
     // Maintain insertion order for consistent output
     order: Vec<String>,
+    // Component instance currently rendering each scope's <style> tag
+    injections: HashMap<String, u64>,
+    // Mounted instances per scope that can take the <style> tag over
+    candidates: HashMap<String, Vec<(u64, Rerender)>>,
+    // Number of mounted component instances per scope
+    mounts: HashMap<String, usize>,
+    // Scopes whose <style> tag was already in the document when hydrating
+    hydrated: HashSet<String>,
+    // Unregister a scope when its last instance unmounts
+    ref_counting: bool,
 }
 
 impl StyleRegistry {
@@ -27,6 +50,11 @@ impl StyleRegistry {
         Self {
             styles: HashMap::with_capacity(32), // Pre-allocate for typical use
             order: Vec::with_capacity(32),
+            injections: HashMap::new(),
+            candidates: HashMap::new(),
+            mounts: HashMap::new(),
+            hydrated: HashSet::new(),
+            ref_counting: false,
         }
     }
 
@@ -52,6 +80,72 @@ impl StyleRegistry {
         }
     }
 
+    /// Removes a scoped style from the registry and the injected output.
+    ///
+    /// Returns `true` if the style was registered.
+    #[inline]
+    pub fn unregister(&mut self, hash: &str) -> bool {
+        if self.styles.remove(hash).is_none() {
+            return false;
+        }
+
+        self.order.retain(|h| h != hash);
+        self.injections.remove(hash);
+        true
+    }
+
+    /// Enables or disables reference-counted registration.
+    ///
+    /// When enabled, a scope is unregistered as soon as the last mounted
+    /// component instance using it is released. Disabled by default, so styles
+    /// stay registered for the lifetime of the process.
+    #[inline]
+    pub fn set_ref_counting(&mut self, enabled: bool) {
+        self.ref_counting = enabled;
+    }
+
+    /// Checks if reference-counted registration is enabled.
+    #[inline]
+    pub fn ref_counting(&self) -> bool {
+        self.ref_counting
+    }
+
+    /// Records a mounted component instance using `hash`, registering `css` if
+    /// the scope was removed while unused.
+    #[inline]
+    pub fn acquire(&mut self, hash: &str, css: &str) {
+        *self.mounts.entry(hash.to_string()).or_insert(0) += 1;
+
+        if !self.styles.contains_key(hash) {
+            self.register(hash.to_string(), css.to_string());
+        }
+    }
+
+    /// Records an unmounted component instance using `hash`.
+    ///
+    /// With reference counting enabled, the style is unregistered when the count
+    /// reaches zero.
+    #[inline]
+    pub fn release(&mut self, hash: &str) {
+        let Some(count) = self.mounts.get_mut(hash) else {
+            return;
+        };
+
+        *count = count.saturating_sub(1);
+        if *count == 0 {
+            self.mounts.remove(hash);
+            if self.ref_counting {
+                self.unregister(hash);
+            }
+        }
+    }
+
+    /// Gets the number of mounted component instances using `hash`.
+    #[inline]
+    pub fn mount_count(&self, hash: &str) -> usize {
+        self.mounts.get(hash).copied().unwrap_or(0)
+    }
+
     /// Gets all registered styles as a single CSS string.
     #[inline]
     pub fn get_all_styles(&self) -> String {
@@ -69,7 +163,8 @@ impl StyleRegistry {
         let mut result = String::with_capacity(total_size);
 
         for hash in &self.order {
-            if let Some(css) = self.styles.get(hash) {
+            // Styles extracted at build time are registered without CSS
+            if let Some(css) = self.styles.get(hash).filter(|css| !css.is_empty()) {
                 result.push_str(css);
                 result.push('\n');
             }
@@ -78,6 +173,110 @@ impl StyleRegistry {
         result
     }
 
+    /// Gets the CSS registered for a single scope.
+    #[inline]
+    pub fn get_style(&self, hash: &str) -> Option<&str> {
+        self.styles.get(hash).map(String::as_str)
+    }
+
+    /// Claims the `<style>` tag for `hash` on behalf of a component instance.
+    ///
+    /// Returns `true` if `owner` is (now) the instance that renders it. Only one
+    /// instance owns a scope at a time, so its CSS is emitted exactly once.
+    ///
+    /// Scopes marked with [`mark_hydrated`](Self::mark_hydrated) are never claimed,
+    /// since their CSS is already in the document.
+    #[inline]
+    pub fn claim_injection(&mut self, hash: &str, owner: u64) -> bool {
+        if self.hydrated.contains(hash) {
+            return false;
+        }
+
+        match self.injections.get(hash) {
+            Some(&current) => current == owner,
+            None => {
+                self.injections.insert(hash.to_string(), owner);
+                true
+            }
+        }
+    }
+
+    /// Registers a mounted instance of `hash` that can render the scope's `<style>`
+    /// tag, with a callback that re-renders it.
+    ///
+    /// When the owner is released, the tag is handed to the first remaining
+    /// candidate, so the styles don't leave the document with the owner.
+    #[inline]
+    pub fn add_injection_candidate(
+        &mut self,
+        hash: &str,
+        owner: u64,
+        rerender: Arc<dyn Fn() + Send + Sync>,
+    ) {
+        self.candidates
+            .entry(hash.to_string())
+            .or_default()
+            .push((owner, Rerender(rerender)));
+    }
+
+    /// Releases a claim made with [`claim_injection`](Self::claim_injection) and
+    /// removes `owner` from the candidates.
+    ///
+    /// If `owner` rendered the tag and another candidate is mounted, the tag is
+    /// claimed for that candidate and its re-render callback is returned. Call it
+    /// after unlocking the registry.
+    #[inline]
+    pub fn release_injection(
+        &mut self,
+        hash: &str,
+        owner: u64,
+    ) -> Option<Arc<dyn Fn() + Send + Sync>> {
+        if let Some(candidates) = self.candidates.get_mut(hash) {
+            candidates.retain(|(id, _)| *id != owner);
+            if candidates.is_empty() {
+                self.candidates.remove(hash);
+            }
+        }
+
+        if self.injections.get(hash) != Some(&owner) {
+            return None;
+        }
+        self.injections.remove(hash);
+
+        let (next, rerender) = self.candidates.get(hash)?.first()?.clone();
+        self.injections.insert(hash.to_string(), next);
+        Some(rerender.0)
+    }
+
+    /// Returns the re-render callback of the instance currently rendering the
+    /// `<style>` tag for `hash`, if it registered one.
+    #[inline]
+    pub fn injection_rerender(&self, hash: &str) -> Option<Arc<dyn Fn() + Send + Sync>> {
+        let owner = self.injections.get(hash)?;
+        self.candidates
+            .get(hash)?
+            .iter()
+            .find(|(id, _)| id == owner)
+            .map(|(_, rerender)| rerender.0.clone())
+    }
+
+    /// Marks `hash` as already present in the document, e.g. in a
+    /// `<style data-dioxus-style="...">` tag emitted by the server.
+    ///
+    /// The server's tags live in the `<head>` and outlast every component, so
+    /// instances keep rendering an empty `<style>` tag for the scope and hydration
+    /// doesn't duplicate the server's styles.
+    #[inline]
+    pub fn mark_hydrated(&mut self, hash: &str) {
+        self.hydrated.insert(hash.to_string());
+    }
+
+    /// Checks if `hash` was marked as already present in the document.
+    #[inline]
+    pub fn is_hydrated(&self, hash: &str) -> bool {
+        self.hydrated.contains(hash)
+    }
+
     /// Checks if a style hash is already registered.
     #[inline]
     pub fn contains(&self, hash: &str) -> bool {
@@ -89,6 +288,10 @@ impl StyleRegistry {
     pub fn clear(&mut self) {
         self.styles.clear();
         self.order.clear();
+        self.injections.clear();
+        self.candidates.clear();
+        self.mounts.clear();
+        self.hydrated.clear();
     }
 
     /// Gets the number of registered styles.
@@ -104,30 +307,322 @@ impl StyleRegistry {
     }
 }
 
+/// Returns the CSS of every registered style.
+///
+/// Inside [`with_registry`](crate::with_registry) this reads the request's
+/// registry instead of the global one.
 #[inline]
 pub fn inject_styles() -> String {
-    STYLE_REGISTRY
+    active_registry()
         .lock()
         .expect("StyleRegistry lock poisoned")
         .get_all_styles()
 }
 
+/// Returns the CSS registered for a single scope.
+#[inline]
+pub fn inject_scope_styles(scope: &str) -> String {
+    active_registry()
+        .lock()
+        .expect("StyleRegistry lock poisoned")
+        .get_style(scope)
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Marks scopes whose styles were already rendered by the server, before
+/// hydrating the client.
+///
+/// Pass the values of the `data-dioxus-style` attributes on the tags matching
+/// [`HYDRATED_STYLE_SELECTOR`]. See [`StyleRegistry::mark_hydrated`].
+pub fn hydrate_scopes<I, S>(scopes: I)
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let registry = active_registry();
+    let mut registry = registry.lock().expect("StyleRegistry lock poisoned");
+    for scope in scopes {
+        registry.mark_hydrated(scope.as_ref());
+    }
+}
+
+/// Callback that re-renders a component instance.
+#[derive(Clone)]
+struct Rerender(Arc<dyn Fn() + Send + Sync>);
+
+impl fmt::Debug for Rerender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Rerender")
+    }
+}
+
+/// Renders `css` as a `<style>` tag carrying its scope in a
+/// `data-dioxus-style` attribute, so the client can find it when hydrating.
+///
+/// `</` in the CSS is escaped as `<\/`, so a `</style>` inside a string can't
+/// end the tag early.
+#[inline]
+pub fn style_tag(scope: &str, css: &str) -> String {
+    format!(
+        "<style {}=\"{}\">{}</style>",
+        STYLE_SCOPE_ATTRIBUTE,
+        scope,
+        css.replace("</", "<\\/")
+    )
+}
+
+/// Enables or disables reference-counted registration on the global registry.
+///
+/// See [`StyleRegistry::set_ref_counting`].
+#[inline]
+pub fn set_ref_counting(enabled: bool) {
+    STYLE_REGISTRY
+        .lock()
+        .expect("StyleRegistry lock poisoned")
+        .set_ref_counting(enabled);
+}
+
+/// Per-component-instance handle used by `#[with_css]` to inject its own scope.
+///
+/// Every instance of a styled component holds one for as long as it is mounted;
+/// only the instance that owns the scope renders the `<style>` tag. Dropping the
+/// owner (on unmount) hands the tag to another mounted instance registered with
+/// [`rerender_with`](Self::rerender_with) and re-renders it, or frees the scope
+/// for the next instance that renders.
+///
+/// Creating a handle counts as mounting the scope and dropping it as unmounting,
+/// so with [`set_ref_counting`] enabled the CSS is removed once the last instance
+/// is gone and registered again on the next mount.
+#[derive(Debug)]
+pub struct StyleInjection {
+    scope: String,
+    id: u64,
+    // Registry the scope was acquired in, released on drop
+    registry: Arc<Mutex<StyleRegistry>>,
+}
+
+impl StyleInjection {
+    /// Creates a handle for one mounted component instance using `style`.
+    #[inline]
+    pub fn new(style: &ScopedStyle) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+        let registry = active_registry();
+        registry
+            .lock()
+            .expect("StyleRegistry lock poisoned")
+            .acquire(&style.scope, &style.current_css());
+
+        Self {
+            scope: style.scope.clone(),
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            registry,
+        }
+    }
+
+    /// Lets this instance take over the `<style>` tag when the instance rendering
+    /// it unmounts; `rerender` must re-render this instance, e.g. Dioxus'
+    /// `schedule_update()`.
+    #[inline]
+    pub fn rerender_with(self, rerender: Arc<dyn Fn() + Send + Sync>) -> Self {
+        self.registry
+            .lock()
+            .expect("StyleRegistry lock poisoned")
+            .add_injection_candidate(&self.scope, self.id, rerender);
+        self
+    }
+
+    /// Returns the scope this handle renders.
+    #[inline]
+    pub fn scope(&self) -> &str {
+        &self.scope
+    }
+
+    /// Returns the CSS this instance should render, or an empty string if another
+    /// instance already renders the scope or the server already did.
+    ///
+    /// While a collection session is active the styles are left to the `<head>`
+    /// tags rendered from the session, so nothing is rendered or claimed.
+    #[inline]
+    pub fn styles(&self) -> String {
+        if crate::style_collector::is_collecting() {
+            return String::new();
+        }
+
+        let mut registry = self.registry.lock().expect("StyleRegistry lock poisoned");
+
+        if registry.claim_injection(&self.scope, self.id) {
+            registry
+                .get_style(&self.scope)
+                .unwrap_or_default()
+                .to_string()
+        } else {
+            String::new()
+        }
+    }
+}
+
+impl Drop for StyleInjection {
+    fn drop(&mut self) {
+        let next_owner = self.registry.lock().ok().and_then(|mut registry| {
+            registry.release(&self.scope);
+            registry.release_injection(&self.scope, self.id)
+        });
+
+        // Re-render the new owner so it renders the tag
+        if let Some(rerender) = next_owner {
+            rerender();
+        }
+    }
+}
+
+/// One of the stylesheets a [`ScopedStyle`] was merged from, as passed to
+/// [`ScopedStyle::from_sources`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StyleSource<'a> {
+    /// Absolute path of a CSS file.
+    File(&'a str),
+    /// Inline CSS.
+    Inline(&'a str),
+}
+
 /// Helper struct for managing a single scoped style instance.
 #[derive(Debug, Clone)]
 pub struct ScopedStyle {
     pub scope: String,
+    // Kept so the style can be registered again after being unregistered
+    css: Arc<str>,
+    // `style` attribute value setting the stylesheet's runtime values
+    style_attr: String,
+    #[cfg(feature = "hot-reload")]
+    source: Option<std::path::PathBuf>,
 }
 
 impl ScopedStyle {
     /// Creates a new scoped style and registers it.
+    ///
+    /// The style is registered globally and in the registry installed with
+    /// [`with_registry`](crate::with_registry), if any. The scope is recorded in
+    /// any active collection session on this thread.
     #[inline]
     pub fn new(scope: String, css: String) -> Self {
+        let shared_css = Arc::from(css.as_str());
+        diagnostics::debug(format_args!(
+            "registered scope {} ({} bytes of CSS)",
+            scope,
+            css.len()
+        ));
+
         STYLE_REGISTRY
             .lock()
             .expect("StyleRegistry lock poisoned")
             .register(scope.clone(), css);
 
-        Self { scope }
+        let style = Self {
+            scope,
+            css: shared_css,
+            style_attr: String::new(),
+            #[cfg(feature = "hot-reload")]
+            source: None,
+        };
+        style.record_use();
+        style
+    }
+
+    /// Creates a new scoped style loaded from a CSS file and registers it.
+    ///
+    /// With the `hot-reload` feature the file is watched, and edits are re-scoped
+    /// into the registry under the same scope. Otherwise this is the same as [`new`].
+    ///
+    /// [`new`]: ScopedStyle::new
+    #[inline]
+    pub fn from_file(scope: String, css: String, path: &str) -> Self {
+        diagnostics::debug(format_args!("loaded CSS file {} for scope {}", path, scope));
+
+        #[cfg(feature = "hot-reload")]
+        {
+            let mut style = Self::new(scope, css);
+            crate::hot_reload::watch(&style.scope, &[StyleSource::File(path)]);
+            style.source = Some(std::path::PathBuf::from(path));
+            style
+        }
+
+        #[cfg(not(feature = "hot-reload"))]
+        {
+            let _ = path;
+            Self::new(scope, css)
+        }
+    }
+
+    /// Creates a new scoped style merged from several stylesheets and registers it.
+    ///
+    /// With the `hot-reload` feature every file in `sources` is watched, and an
+    /// edit to any of them re-merges and re-scopes the stylesheet under the same
+    /// scope. Otherwise this is the same as [`new`].
+    ///
+    /// [`new`]: ScopedStyle::new
+    #[inline]
+    pub fn from_sources(scope: String, css: String, sources: &[StyleSource<'_>]) -> Self {
+        #[cfg(feature = "hot-reload")]
+        {
+            let mut style = Self::new(scope, css);
+            crate::hot_reload::watch(&style.scope, sources);
+            style.source = sources.iter().find_map(|source| match source {
+                StyleSource::File(path) => Some(std::path::PathBuf::from(path)),
+                StyleSource::Inline(_) => None,
+            });
+            style
+        }
+
+        #[cfg(not(feature = "hot-reload"))]
+        {
+            let _ = sources;
+            Self::new(scope, css)
+        }
+    }
+
+    /// Returns the CSS file this style was loaded from, if any (the first one
+    /// for a style merged from several files).
+    #[cfg(feature = "hot-reload")]
+    #[inline]
+    pub fn source_path(&self) -> Option<&std::path::Path> {
+        self.source.as_deref()
+    }
+
+    /// Returns a handle to this style for one use (e.g. one render).
+    ///
+    /// Like `clone`, but also records the scope in any active collection session
+    /// on this thread and registers it in the registry installed with
+    /// [`with_registry`](crate::with_registry). The style macros call this on
+    /// every evaluation.
+    #[inline]
+    pub fn instantiate(&self) -> Self {
+        self.record_use();
+        self.clone()
+    }
+
+    /// Records a use of this style in the current render.
+    fn record_use(&self) {
+        let css = self.current_css();
+        if let Some(registry) = local_registry() {
+            let mut registry = registry.lock().expect("StyleRegistry lock poisoned");
+            if !registry.contains(&self.scope) {
+                registry.register(self.scope.clone(), css.to_string());
+            }
+        }
+
+        crate::style_collector::record_scope(&self.scope, &css);
+    }
+
+    /// Returns the CSS to register for this style: the last hot-reloaded version,
+    /// if any, instead of the one it was compiled with.
+    fn current_css(&self) -> Arc<str> {
+        #[cfg(feature = "hot-reload")]
+        if let Some(css) = crate::hot_reload::reloaded_css(&self.scope) {
+            return css;
+        }
+        self.css.clone()
     }
 
     /// Returns the scope prefix for use in class names.
@@ -135,6 +630,21 @@ impl ScopedStyle {
     pub fn scope(&self) -> &str {
         &self.scope
     }
+
+    /// Returns the `style` attribute value that sets the stylesheet's runtime
+    /// values, e.g. `"--sc_abc_0: 120px;"`, or an empty string if it has none.
+    #[inline]
+    pub fn style_attr(&self) -> &str {
+        &self.style_attr
+    }
+
+    /// Sets the value returned by [`style_attr`](Self::style_attr). The style
+    /// macros call this with the current runtime values on every evaluation.
+    #[inline]
+    pub fn with_style_attr(mut self, style_attr: String) -> Self {
+        self.style_attr = style_attr;
+        self
+    }
 }
 
 impl std::fmt::Display for ScopedStyle {
@@ -174,4 +684,145 @@ mod tests {
         assert!(a_pos < b_pos);
         assert!(b_pos < c_pos);
     }
+
+    #[test]
+    fn test_registry_injection_claims() {
+        let mut registry = StyleRegistry::new();
+        registry.register("a".to_string(), "css_a".to_string());
+
+        assert!(registry.claim_injection("a", 1));
+        assert!(registry.claim_injection("a", 1));
+        assert!(!registry.claim_injection("a", 2));
+
+        // Releasing by a non-owner is a no-op
+        registry.release_injection("a", 2);
+        assert!(!registry.claim_injection("a", 2));
+
+        registry.release_injection("a", 1);
+        assert!(registry.claim_injection("a", 2));
+        assert_eq!(registry.get_style("a"), Some("css_a"));
+    }
+
+    #[test]
+    fn test_style_injection_renders_once() {
+        let style = ScopedStyle::new("sc_injection".to_string(), "css_injection".to_string());
+
+        let first = StyleInjection::new(&style);
+        let second = StyleInjection::new(&style);
+
+        assert_eq!(first.styles(), "css_injection");
+        assert_eq!(second.styles(), "");
+        assert_eq!(first.styles(), "css_injection");
+
+        // The next instance to render takes over after the owner unmounts
+        drop(first);
+        assert_eq!(second.styles(), "css_injection");
+        assert_eq!(inject_scope_styles("sc_injection"), "css_injection");
+    }
+
+    #[test]
+    fn test_owner_drop_hands_over_injection() {
+        use std::sync::atomic::AtomicUsize;
+
+        let style = ScopedStyle::new("sc_handover".to_string(), "css_handover".to_string());
+        let rerenders: Vec<Arc<AtomicUsize>> = (0..3).map(|_| Arc::default()).collect();
+        let mut instances: Vec<StyleInjection> = rerenders
+            .iter()
+            .map(|count| {
+                let count = count.clone();
+                StyleInjection::new(&style).rerender_with(Arc::new(move || {
+                    count.fetch_add(1, Ordering::SeqCst);
+                }))
+            })
+            .collect();
+
+        assert_eq!(instances[0].styles(), "css_handover");
+        assert_eq!(instances[1].styles(), "");
+        assert_eq!(instances[2].styles(), "");
+
+        // Unmounting the owner while others are mounted re-renders the next one
+        drop(instances.remove(0));
+        assert_eq!(rerenders[1].load(Ordering::SeqCst), 1);
+        assert_eq!(rerenders[2].load(Ordering::SeqCst), 0);
+        assert_eq!(instances[1].styles(), "");
+        assert_eq!(instances[0].styles(), "css_handover");
+
+        // Unmounting a non-owner doesn't re-render anything
+        drop(instances.remove(1));
+        assert_eq!(rerenders[1].load(Ordering::SeqCst), 1);
+        assert_eq!(instances[0].styles(), "css_handover");
+    }
+
+    #[test]
+    fn test_registry_unregister() {
+        let mut registry = StyleRegistry::new();
+        registry.register("a".to_string(), "css_a".to_string());
+        registry.register("b".to_string(), "css_b".to_string());
+
+        assert!(registry.unregister("a"));
+        assert!(!registry.unregister("a"));
+        assert!(!registry.contains("a"));
+        assert_eq!(registry.get_all_styles(), "css_b\n");
+    }
+
+    #[test]
+    fn test_registry_ref_counting() {
+        let mut registry = StyleRegistry::new();
+        registry.set_ref_counting(true);
+
+        registry.acquire("a", "css_a");
+        registry.acquire("a", "css_a");
+        assert_eq!(registry.mount_count("a"), 2);
+
+        registry.release("a");
+        assert!(registry.contains("a"));
+
+        registry.release("a");
+        assert_eq!(registry.mount_count("a"), 0);
+        assert!(!registry.contains("a"));
+        assert!(registry.get_all_styles().is_empty());
+
+        // Mounting again registers the style again
+        registry.acquire("a", "css_a");
+        assert_eq!(registry.get_style("a"), Some("css_a"));
+    }
+
+    #[test]
+    fn test_registry_without_ref_counting_keeps_styles() {
+        let mut registry = StyleRegistry::new();
+
+        registry.register("a".to_string(), "css_a".to_string());
+        registry.acquire("a", "css_a");
+        registry.release("a");
+
+        assert!(registry.contains("a"));
+    }
+
+    #[test]
+    fn test_hydrated_scopes_are_not_injected_again() {
+        let mut registry = StyleRegistry::new();
+        registry.register("a".to_string(), "css_a".to_string());
+        registry.mark_hydrated("a");
+
+        registry.acquire("a", "css_a");
+        assert!(!registry.claim_injection("a", 1));
+        assert!(registry.is_hydrated("a"));
+
+        // The server's tag stays in the <head> after the last instance unmounts
+        registry.release("a");
+        assert!(registry.is_hydrated("a"));
+        assert!(!registry.claim_injection("a", 2));
+    }
+
+    #[test]
+    fn test_style_tag() {
+        assert_eq!(
+            style_tag("sc_a", ".sc_a_b{color:red}"),
+            "<style data-dioxus-style=\"sc_a\">.sc_a_b{color:red}</style>"
+        );
+        assert_eq!(
+            style_tag("sc_a", ".sc_a_b::after{content:\"</style>\"}"),
+            "<style data-dioxus-style=\"sc_a\">.sc_a_b::after{content:\"<\\/style>\"}</style>"
+        );
+    }
 }
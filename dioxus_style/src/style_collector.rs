@@ -0,0 +1,195 @@
+//! Per-render style collection for server-side rendering.
+//!
+//! A collection session records every scope used on the current thread while it
+//! is active, so an SSR response can embed only the CSS its render needed.
+
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use crate::registry_scope::active_registry;
+use crate::runtime_injector::{style_tag, StyleRegistry};
+
+/// Scopes recorded by one session, with the CSS they were created with.
+type Session = Vec<(String, Arc<str>)>;
+
+thread_local! {
+    /// Active collection sessions on this thread, innermost last.
+    static SESSIONS: RefCell<Vec<Session>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Records that `scope` was used, in every active session on this thread.
+#[inline]
+pub(crate) fn record_scope(scope: &str, css: &Arc<str>) {
+    SESSIONS.with(|sessions| {
+        for session in sessions.borrow_mut().iter_mut() {
+            if !session.iter().any(|(recorded, _)| recorded == scope) {
+                session.push((scope.to_string(), css.clone()));
+            }
+        }
+    });
+}
+
+/// Checks if a collection session is active on this thread.
+#[inline]
+pub(crate) fn is_collecting() -> bool {
+    SESSIONS.with(|sessions| !sessions.borrow().is_empty())
+}
+
+/// The CSS used during a collection session.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CollectedStyles {
+    /// Scoped CSS of every used scope, in first-use order.
+    pub css: String,
+    /// The scope ids that were used, in first-use order.
+    pub scopes: Vec<String>,
+    // CSS of each scope in `scopes`, for rendering one tag per scope
+    sheets: Vec<String>,
+}
+
+impl CollectedStyles {
+    /// Renders one `<style data-dioxus-style="...">` tag per used scope.
+    ///
+    /// Embed these instead of `css` when the client hydrates, and pass the scope
+    /// ids to [`hydrate_scopes`](crate::hydrate_scopes) so components don't inject
+    /// the same styles again.
+    pub fn style_tags(&self) -> String {
+        self.scopes
+            .iter()
+            .zip(&self.sheets)
+            .map(|(scope, css)| style_tag(scope, css))
+            .collect()
+    }
+}
+
+/// An active collection session, created by [`StyleRegistry::start_collection`].
+///
+/// Sessions are thread-local: only styles used on the thread that started the
+/// session are recorded. Dropping the session without calling [`finish`] discards it.
+///
+/// [`finish`]: StyleCollection::finish
+#[derive(Debug)]
+pub struct StyleCollection {
+    depth: usize,
+}
+
+impl StyleRegistry {
+    /// Starts recording the scopes used on the current thread.
+    ///
+    /// Wrap a single server-side render with a session to get just the CSS it needs:
+    ///
+    /// ```
+    /// use dioxus_style::{scoped_style, StyleRegistry};
+    ///
+    /// let collection = StyleRegistry::start_collection();
+    /// // Render here; every styled component used records its scope
+    /// let css = scoped_style!(".title { font-weight: bold; }");
+    /// let styles = collection.finish();
+    ///
+    /// assert_eq!(styles.scopes, vec![css.scope().to_string()]);
+    /// // Embed `styles.css` in the <head>
+    /// ```
+    pub fn start_collection() -> StyleCollection {
+        let depth = SESSIONS.with(|sessions| {
+            let mut sessions = sessions.borrow_mut();
+            sessions.push(Vec::new());
+            sessions.len()
+        });
+
+        StyleCollection { depth }
+    }
+}
+
+impl StyleCollection {
+    /// Ends the session and returns the CSS of every scope used during it.
+    pub fn finish(self) -> CollectedStyles {
+        let recorded = self.take();
+        let registry = active_registry();
+        let registry = registry.lock().expect("StyleRegistry lock poisoned");
+
+        let mut collected = CollectedStyles::default();
+        for (scope, css) in recorded {
+            // Prefer the registry so hot-reloaded styles are picked up
+            let css = registry.get_style(&scope).unwrap_or(&css);
+            collected.css.push_str(css);
+            collected.css.push('\n');
+            collected.sheets.push(css.to_string());
+            collected.scopes.push(scope);
+        }
+
+        collected
+    }
+
+    /// Removes this session (and any nested sessions left open) from the stack.
+    fn take(&self) -> Session {
+        SESSIONS.with(|sessions| {
+            let mut sessions = sessions.borrow_mut();
+            if sessions.len() < self.depth {
+                return Vec::new();
+            }
+            sessions.truncate(self.depth);
+            sessions.pop().unwrap_or_default()
+        })
+    }
+}
+
+impl Drop for StyleCollection {
+    fn drop(&mut self) {
+        self.take();
+    }
+}
+
+/// Runs `render` inside a collection session and returns its result together
+/// with the styles it used.
+pub fn collect_styles<R>(render: impl FnOnce() -> R) -> (R, CollectedStyles) {
+    let collection = StyleRegistry::start_collection();
+    let output = render();
+    (output, collection.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ScopedStyle;
+
+    #[test]
+    fn test_collects_only_used_styles() {
+        let used = ScopedStyle::new("sc_collect_a".to_string(), "css_a".to_string());
+        let _unused = ScopedStyle::new("sc_collect_b".to_string(), "css_b".to_string());
+
+        let ((), styles) = collect_styles(|| {
+            used.instantiate();
+            used.instantiate();
+            ScopedStyle::new("sc_collect_c".to_string(), "css_c".to_string());
+        });
+
+        assert_eq!(styles.scopes, vec!["sc_collect_a", "sc_collect_c"]);
+        assert_eq!(styles.css, "css_a\ncss_c\n");
+        assert_eq!(
+            styles.style_tags(),
+            "<style data-dioxus-style=\"sc_collect_a\">css_a</style><style data-dioxus-style=\"sc_collect_c\">css_c</style>"
+        );
+    }
+
+    #[test]
+    fn test_nested_sessions() {
+        let style = ScopedStyle::new("sc_collect_nested".to_string(), "css_n".to_string());
+
+        let outer = StyleRegistry::start_collection();
+        let ((), inner) = collect_styles(|| {
+            style.instantiate();
+        });
+
+        assert_eq!(inner.scopes, vec!["sc_collect_nested"]);
+        assert_eq!(outer.finish().scopes, vec!["sc_collect_nested"]);
+    }
+
+    #[test]
+    fn test_dropped_session_is_discarded() {
+        let style = ScopedStyle::new("sc_collect_drop".to_string(), "css_d".to_string());
+
+        drop(StyleRegistry::start_collection());
+        style.instantiate();
+
+        SESSIONS.with(|sessions| assert!(sessions.borrow().is_empty()));
+    }
+}
@@ -0,0 +1,341 @@
+//! Renders components styled with `#[with_css]` and `component_with_css!`.
+//!
+//! The style registry is global, so every test uses its own stylesheet.
+
+#![allow(non_snake_case)]
+
+use std::fmt::Display;
+
+use dioxus::dioxus_core::{
+    AttributeValue, DynamicNode, Mutation, Mutations, TemplateAttribute, TemplateNode,
+};
+use dioxus::prelude::*;
+use dioxus_style::{component_with_css, scoped_style, with_css};
+
+/// Renders `app` and returns the edits.
+fn rebuild(app: fn() -> Element) -> (VirtualDom, Vec<Mutation>) {
+    let mut dom = VirtualDom::new(app);
+    let mut mutations = Mutations::default();
+    dom.rebuild(&mut mutations);
+    (dom, mutations.edits)
+}
+
+/// Applies pending updates and returns the edits.
+fn render(dom: &mut VirtualDom) -> Vec<Mutation> {
+    let mut mutations = Mutations::default();
+    dom.render_immediate(&mut mutations);
+    mutations.edits
+}
+
+/// Values of the attribute `name` set by `edits`, in order.
+fn attributes(edits: &[Mutation], name: &str) -> Vec<String> {
+    edits
+        .iter()
+        .filter_map(|edit| match edit {
+            Mutation::SetAttribute {
+                name: set,
+                value: AttributeValue::Text(value),
+                ..
+            } if *set == name => Some(value.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Values of the static attribute `name` in the templates rendered by `dom`.
+fn static_attributes(dom: &VirtualDom, name: &str) -> Vec<String> {
+    fn visit_template(node: &TemplateNode, name: &str, out: &mut Vec<String>) {
+        if let TemplateNode::Element {
+            attrs, children, ..
+        } = node
+        {
+            for attr in attrs.iter() {
+                match attr {
+                    TemplateAttribute::Static {
+                        name: set, value, ..
+                    } if *set == name => out.push(value.to_string()),
+                    _ => {}
+                }
+            }
+            for child in children.iter() {
+                visit_template(child, name, out);
+            }
+        }
+    }
+
+    fn visit(node: &VNode, name: &str, out: &mut Vec<String>) {
+        for root in node.template.roots.iter() {
+            visit_template(root, name, out);
+        }
+        for dynamic in node.dynamic_nodes.iter() {
+            if let DynamicNode::Fragment(nodes) = dynamic {
+                for node in nodes {
+                    visit(node, name, out);
+                }
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    let mut id = 0;
+    while let Some(scope) = dom.get_scope(ScopeId(id)) {
+        visit(scope.root_node(), name, &mut out);
+        id += 1;
+    }
+    out
+}
+
+/// Contents of the `<style>` tags rendered by `edits`, in order.
+fn style_contents(edits: &[Mutation]) -> Vec<String> {
+    attributes(edits, "dangerous_inner_html")
+}
+
+#[with_css(inline = ".item { color: red; }")]
+fn Item(n: u32) -> Element {
+    rsx! { li { class: css.item(), "{n}" } }
+}
+
+static ITEMS: GlobalSignal<Vec<u32>> = Signal::global(|| vec![1, 2, 3]);
+
+fn ItemList() -> Element {
+    rsx! {
+        for n in ITEMS() {
+            Item { key: "{n}", n }
+        }
+    }
+}
+
+#[test]
+fn test_one_instance_renders_the_styles() {
+    let (_dom, edits) = rebuild(ItemList);
+    let css = scoped_style!(".item { color: red; }");
+
+    // Every instance renders a tag, only the first one fills it
+    let contents = style_contents(&edits);
+    assert_eq!(contents.len(), 3);
+    assert!(contents[0].contains(&format!(".{}_item", css.scope())));
+    assert_eq!(contents[1..], ["", ""]);
+
+    assert_eq!(
+        attributes(&edits, "data-dioxus-style"),
+        vec![css.scope(); 3]
+    );
+    assert_eq!(
+        attributes(&edits, "class"),
+        vec![format!("{}_item", css.scope()); 3]
+    );
+}
+
+#[with_css(inline = ".row { color: green; }")]
+fn Row(n: u32) -> Element {
+    rsx! { li { class: css.row(), "{n}" } }
+}
+
+static ROWS: GlobalSignal<Vec<u32>> = Signal::global(|| vec![1, 2, 3]);
+
+fn RowList() -> Element {
+    rsx! {
+        for n in ROWS() {
+            Row { key: "{n}", n }
+        }
+    }
+}
+
+#[test]
+fn test_unmounting_the_owner_hands_over_the_styles() {
+    let (mut dom, _) = rebuild(RowList);
+    let css = scoped_style!(".row { color: green; }");
+
+    dom.in_runtime(|| ROWS.write().remove(0));
+    dom.mark_dirty(ScopeId::APP);
+    assert!(style_contents(&render(&mut dom)).is_empty());
+
+    // The next instance re-renders and takes the styles over
+    let contents = style_contents(&render(&mut dom));
+    assert_eq!(contents.len(), 1);
+    assert!(contents[0].contains(&format!(".{}_row", css.scope())));
+}
+
+#[with_css(inline = ".panel { margin: 0; }", inject = false, name = styles)]
+fn Panel() -> Element {
+    rsx! { div { class: styles.panel() } }
+}
+
+#[test]
+fn test_inject_false_renders_no_style_tag() {
+    let (_dom, edits) = rebuild(|| rsx! { Panel {} });
+    let css = scoped_style!(".panel { margin: 0; }");
+
+    assert!(style_contents(&edits).is_empty());
+    assert_eq!(
+        attributes(&edits, "class"),
+        [format!("{}_panel", css.scope())]
+    );
+    assert!(dioxus_style::inject_styles().contains(&format!(".{}_panel", css.scope())));
+}
+
+#[with_css(inline = ".label { font-weight: bold; }")]
+fn Label<T: Display + Clone + PartialEq + 'static>(value: T) -> Element {
+    rsx! { span { class: css.label(), "{value}" } }
+}
+
+component_with_css! {
+    css: ".card { padding: {padding}px; }",
+    /// A card with a runtime padding
+    fn Card(padding: u32) -> Element {
+        rsx! {
+            div { class: css.card(), style: css.style_attr(),
+                Label { value: padding }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_generic_and_function_like_components() {
+    let (_dom, edits) = rebuild(|| rsx! { Card { padding: 12 } });
+    let padding = 12;
+    let card = scoped_style!(".card { padding: {padding}px; }");
+    let label = scoped_style!(".label { font-weight: bold; }");
+
+    let contents = style_contents(&edits);
+    assert_eq!(contents.len(), 2);
+    assert!(contents[0].contains(&format!("var(--{}_0)", card.scope())));
+    assert!(contents[1].contains(&format!(".{}_label", label.scope())));
+    assert_eq!(
+        attributes(&edits, "style"),
+        [format!("--{}_0: 12px;", card.scope())]
+    );
+}
+
+#[with_css(inline = ".hydrated { color: blue; }")]
+fn Hydrated() -> Element {
+    rsx! { p { class: css.hydrated() } }
+}
+
+#[test]
+fn test_hydrated_scopes_keep_an_empty_style_tag() {
+    let css = scoped_style!(".hydrated { color: blue; }");
+    dioxus_style::hydrate_scopes([css.scope()]);
+
+    let (_dom, edits) = rebuild(|| rsx! { Hydrated {} });
+    assert_eq!(style_contents(&edits), [""]);
+    assert_eq!(attributes(&edits, "data-dioxus-style"), [css.scope()]);
+}
+
+#[with_css("tests/styles/progress.css")]
+fn Progress(percent: u32, color: String) -> Element {
+    rsx! { div { class: css.progress(), style: css.style_attr() } }
+}
+
+#[test]
+fn test_css_files_are_interpolated() {
+    let (_dom, edits) = rebuild(|| {
+        rsx! { Progress { percent: 40, color: "teal" } }
+    });
+    let (percent, color) = (40, "teal");
+    let css = scoped_style!("tests/styles/progress.css");
+
+    // `{props}` in the file's comment is not an interpolation
+    let contents = style_contents(&edits);
+    assert!(contents[0].contains(&format!("var(--{}_1)", css.scope())));
+    assert_eq!(
+        attributes(&edits, "style"),
+        [format!("--{0}_0: 40%; --{0}_1: teal;", css.scope())]
+    );
+}
+
+#[with_css(inline = ".early { color: olive; }")]
+fn Early(flag: bool) -> Element {
+    if flag {
+        return rsx! { p { class: css.early(), "early" } };
+    }
+    rsx! { p { class: css.early(), "late" } }
+}
+
+#[with_css(inline = ".fallible { color: maroon; }")]
+fn Fallible(input: &'static str) -> Element {
+    let n: u32 = input.parse()?;
+    rsx! { p { class: css.fallible(), "{n}" } }
+}
+
+#[test]
+fn test_early_returns_still_render_the_styles() {
+    let (_dom, edits) = rebuild(|| {
+        rsx! {
+            Early { flag: true }
+            Early { flag: false }
+        }
+    });
+    let css = scoped_style!(".early { color: olive; }");
+
+    let contents = style_contents(&edits);
+    assert_eq!(contents.len(), 2);
+    assert!(contents[0].contains(&format!(".{}_early", css.scope())));
+    assert_eq!(contents[1], "");
+}
+
+#[test]
+fn test_failed_renders_do_not_claim_the_styles() {
+    let (_dom, edits) = rebuild(|| {
+        rsx! {
+            Fallible { input: "x" }
+            Fallible { input: "7" }
+        }
+    });
+    let css = scoped_style!(".fallible { color: maroon; }");
+
+    // The instance that failed with `?` renders nothing, the next one the styles
+    let contents = style_contents(&edits);
+    assert_eq!(contents.len(), 1);
+    assert!(contents[0].contains(&format!(".{}_fallible", css.scope())));
+}
+
+fn scope_of(style: &dioxus_style::ScopedStyle) -> String {
+    style.scope().to_string()
+}
+
+#[test]
+fn test_accessor_wrapper_converts_to_scoped_style() {
+    let css = scoped_style!(".wrapped { color: gray; }");
+    let scope = scope_of(css.as_ref());
+    assert_eq!(css.wrapped(), format!("{}_wrapped", scope));
+
+    let style: dioxus_style::ScopedStyle = css.clone().into_inner();
+    assert_eq!(style.scope(), scope);
+    let style: dioxus_style::ScopedStyle = css.into();
+    assert_eq!(style.scope(), scope);
+}
+
+#[with_css(inline = ".collected { color: navy; }")]
+fn Collected() -> Element {
+    rsx! { p { class: css.collected() } }
+}
+
+#[test]
+fn test_collected_styles_are_left_to_the_head() {
+    let ((_dom, edits), styles) =
+        dioxus_style::collect_styles(|| rebuild(|| rsx! { Collected {} }));
+    let css = scoped_style!(".collected { color: navy; }");
+
+    // The body tag stays empty, the <head> tags carry the only copy
+    assert_eq!(style_contents(&edits), [""]);
+    assert_eq!(styles.scopes, [css.scope()]);
+    assert!(styles
+        .style_tags()
+        .contains(&format!(".{}_collected", css.scope())));
+}
+
+#[with_css(inline = ".tagged { margin: 0; } span { color: purple; }")]
+fn Tagged() -> Element {
+    rsx! { div { class: css.tagged(), span { "tagged" } } }
+}
+
+#[test]
+fn test_elements_carry_the_data_scope_attribute() {
+    let (dom, _) = rebuild(|| rsx! { Tagged {} });
+    let css = scoped_style!(".tagged { margin: 0; } span { color: purple; }");
+
+    // Both the `div` and the nested `span`, but not the `<style>` tag
+    assert_eq!(static_attributes(&dom, "data-scope"), vec![css.scope(); 2]);
+}
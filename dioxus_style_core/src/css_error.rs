@@ -0,0 +1,350 @@
+//! Syntax checking for CSS input.
+//!
+//! Reports malformed CSS with line and column information so the macros can
+//! turn it into a compile error instead of emitting broken styles.
+
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// The kind of syntax error found in a stylesheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CssErrorKind {
+    /// A `{` without a matching `}`.
+    UnclosedBlock,
+    /// A `}` without a matching `{`.
+    StrayClosingBrace,
+    /// A string literal that is not closed before the end of the line.
+    UnterminatedString,
+    /// A `/*` comment without a closing `*/`.
+    UnterminatedComment,
+    /// A block with no selector, or an empty entry in a selector list.
+    EmptySelector,
+    /// A top-level selector that is not followed by a `{ ... }` block.
+    MissingBlock,
+    /// A Rust interpolation in an at-rule prelude, where custom properties
+    /// can't be used.
+    InterpolationInPrelude,
+}
+
+impl fmt::Display for CssErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            CssErrorKind::UnclosedBlock => "unclosed block: missing `}`",
+            CssErrorKind::StrayClosingBrace => "unexpected `}` without matching `{`",
+            CssErrorKind::UnterminatedString => "unterminated string",
+            CssErrorKind::UnterminatedComment => "unterminated comment: missing `*/`",
+            CssErrorKind::EmptySelector => "empty selector",
+            CssErrorKind::MissingBlock => "expected `{` after selector",
+            CssErrorKind::InterpolationInPrelude => {
+                "interpolations are not supported in at-rule preludes"
+            }
+        };
+        f.write_str(message)
+    }
+}
+
+/// A syntax error with its 1-based position in the CSS source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CssError {
+    pub kind: CssErrorKind,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl CssError {
+    /// Formats the error as `source:line:column: message`.
+    pub fn with_source(&self, source: &str) -> String {
+        format!("{}:{}:{}: {}", source, self.line, self.column, self.kind)
+    }
+}
+
+impl fmt::Display for CssError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.kind
+        )
+    }
+}
+
+impl std::error::Error for CssError {}
+
+/// Checks a complete stylesheet for syntax errors.
+pub fn check_syntax(css: &str) -> Result<(), CssError> {
+    check(css, false)
+}
+
+/// Checks the body of a single declaration block (the contents of `css!`).
+pub fn check_declarations(css: &str) -> Result<(), CssError> {
+    check(css, true)
+}
+
+/// Character iterator that tracks the current line and column.
+struct Cursor<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(css: &'a str) -> Self {
+        Self {
+            chars: css.chars().peekable(),
+            line: 1,
+            column: 0,
+        }
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.chars.next()?;
+        if ch == '\n' {
+            self.line += 1;
+            self.column = 0;
+        } else {
+            self.column += 1;
+        }
+        Some(ch)
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    /// Position of the character most recently returned by `next`.
+    fn position(&self) -> (usize, usize) {
+        (self.line, self.column)
+    }
+}
+
+fn error(kind: CssErrorKind, (line, column): (usize, usize)) -> CssError {
+    CssError { kind, line, column }
+}
+
+/// Scans `css` tracking blocks, strings and comments.
+///
+/// With `in_block` set, the input is treated as if it were already inside a
+/// `{ ... }` block, so bare declarations are allowed at the top level.
+fn check(css: &str, in_block: bool) -> Result<(), CssError> {
+    let mut cursor = Cursor::new(css);
+    // Positions of the currently open `{`
+    let mut open_blocks: Vec<(usize, usize)> = Vec::with_capacity(8);
+    // Text since the last `{`, `}` or `;` and where it started
+    let mut prelude = String::with_capacity(64);
+    let mut prelude_start = None;
+
+    while let Some(ch) = cursor.next() {
+        let position = cursor.position();
+
+        match ch {
+            '/' if cursor.peek() == Some('*') => {
+                cursor.next();
+                let mut closed = false;
+                while let Some(ch) = cursor.next() {
+                    if ch == '*' && cursor.peek() == Some('/') {
+                        cursor.next();
+                        closed = true;
+                        break;
+                    }
+                }
+                if !closed {
+                    return Err(error(CssErrorKind::UnterminatedComment, position));
+                }
+            }
+
+            '"' | '\'' => {
+                let mut closed = false;
+                while let Some(next_ch) = cursor.next() {
+                    match next_ch {
+                        '\\' => {
+                            cursor.next();
+                        }
+                        '\n' => break,
+                        c if c == ch => {
+                            closed = true;
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+                if !closed {
+                    return Err(error(CssErrorKind::UnterminatedString, position));
+                }
+                prelude_start.get_or_insert(position);
+                prelude.push(ch);
+            }
+
+            '{' => {
+                let selector = prelude.trim();
+                if selector.is_empty() {
+                    return Err(error(CssErrorKind::EmptySelector, position));
+                }
+                if !selector.starts_with('@') && has_empty_entry(selector) {
+                    return Err(error(
+                        CssErrorKind::EmptySelector,
+                        prelude_start.unwrap_or(position),
+                    ));
+                }
+
+                open_blocks.push(position);
+                prelude.clear();
+                prelude_start = None;
+            }
+
+            '}' => {
+                if open_blocks.pop().is_none() {
+                    return Err(error(CssErrorKind::StrayClosingBrace, position));
+                }
+                prelude.clear();
+                prelude_start = None;
+            }
+
+            ';' => {
+                let statement = prelude.trim();
+                if open_blocks.is_empty()
+                    && !in_block
+                    && !statement.is_empty()
+                    && !statement.starts_with('@')
+                {
+                    return Err(error(
+                        CssErrorKind::MissingBlock,
+                        prelude_start.unwrap_or(position),
+                    ));
+                }
+                prelude.clear();
+                prelude_start = None;
+            }
+
+            _ => {
+                if !ch.is_whitespace() {
+                    prelude_start.get_or_insert(position);
+                }
+                prelude.push(ch);
+            }
+        }
+    }
+
+    if let Some(&position) = open_blocks.last() {
+        return Err(error(CssErrorKind::UnclosedBlock, position));
+    }
+
+    let trailing = prelude.trim();
+    if !in_block && !trailing.is_empty() && !trailing.starts_with('@') {
+        if let Some(position) = prelude_start {
+            return Err(error(CssErrorKind::MissingBlock, position));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks if a selector list has an empty entry (`.a, , .b` or `.a,`).
+fn has_empty_entry(selector: &str) -> bool {
+    let mut depth = 0i32;
+    let mut entry_empty = true;
+
+    for ch in selector.chars() {
+        match ch {
+            '(' | '[' => {
+                depth += 1;
+                entry_empty = false;
+            }
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                if entry_empty {
+                    return true;
+                }
+                entry_empty = true;
+            }
+            c if !c.is_whitespace() => entry_empty = false,
+            _ => {}
+        }
+    }
+
+    entry_empty
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind_at(css: &str) -> (CssErrorKind, usize, usize) {
+        let err = check_syntax(css).unwrap_err();
+        (err.kind, err.line, err.column)
+    }
+
+    #[test]
+    fn test_valid_css() {
+        let css = r#"
+            /* comment with { brace */
+            .a, .b:not(.c) { content: "}"; }
+            @import url("x.css");
+            @media (max-width: 600px) { .a { color: red; } }
+        "#;
+        assert!(check_syntax(css).is_ok());
+    }
+
+    #[test]
+    fn test_unclosed_block() {
+        let css = ".a { color: red; }\n.b {\n  color: blue;\n";
+        assert_eq!(kind_at(css), (CssErrorKind::UnclosedBlock, 2, 4));
+    }
+
+    #[test]
+    fn test_stray_closing_brace() {
+        let css = ".a { color: red; } }";
+        assert_eq!(kind_at(css), (CssErrorKind::StrayClosingBrace, 1, 20));
+    }
+
+    #[test]
+    fn test_unterminated_string() {
+        let css = ".a {\n  content: \"oops;\n}";
+        assert_eq!(kind_at(css), (CssErrorKind::UnterminatedString, 2, 12));
+    }
+
+    #[test]
+    fn test_unterminated_comment() {
+        let css = ".a { color: red; }\n/* never closed";
+        assert_eq!(kind_at(css), (CssErrorKind::UnterminatedComment, 2, 1));
+    }
+
+    #[test]
+    fn test_empty_selector() {
+        assert_eq!(
+            kind_at("{ color: red; }"),
+            (CssErrorKind::EmptySelector, 1, 1)
+        );
+        assert_eq!(
+            kind_at("\n.a, { color: red; }"),
+            (CssErrorKind::EmptySelector, 2, 1)
+        );
+    }
+
+    #[test]
+    fn test_missing_block() {
+        assert_eq!(
+            kind_at(".a color: red;"),
+            (CssErrorKind::MissingBlock, 1, 1)
+        );
+        assert_eq!(kind_at(".a { }\n.b"), (CssErrorKind::MissingBlock, 2, 1));
+    }
+
+    #[test]
+    fn test_declarations() {
+        assert!(check_declarations("color: red; padding: 4px;").is_ok());
+        assert_eq!(
+            check_declarations("color: red; }").unwrap_err().kind,
+            CssErrorKind::StrayClosingBrace
+        );
+    }
+
+    #[test]
+    fn test_with_source() {
+        let err = check_syntax(".a {").unwrap_err();
+        assert_eq!(
+            err.with_source("button.css"),
+            "button.css:1:4: unclosed block: missing `}`"
+        );
+    }
+}
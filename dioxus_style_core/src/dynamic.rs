@@ -0,0 +1,309 @@
+//! Runtime values in stylesheets.
+//!
+//! A Rust interpolation in a declaration value, like `width: {width}px`, marks a
+//! value only known at runtime. The CSS token containing it is replaced with
+//! `var(--{scope}_{n})`, and the macros generate a `style` attribute string that
+//! sets the custom property from the runtime value.
+
+use crate::css_error::{CssError, CssErrorKind};
+
+/// A CSS value set at runtime through a custom property.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynamicValue {
+    /// The custom property standing in for the value, e.g. `--sc_abc_0`.
+    pub property: String,
+    /// The value as a Rust format string with one `{}` per expression, e.g. `{}px`.
+    pub format: String,
+    /// The interpolated Rust expressions, in order.
+    pub expressions: Vec<String>,
+}
+
+/// CSS with its interpolations replaced by custom properties.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynamicCss {
+    /// The CSS, referencing each dynamic value with `var()`.
+    pub css: String,
+    /// The dynamic values, in the order they appear.
+    pub values: Vec<DynamicValue>,
+}
+
+/// Replaces the interpolations in `css` with `var(--{scope}_{n})`.
+///
+/// An interpolation is a `{...}` in a declaration value holding a Rust expression
+/// without `:`, `;` or braces, so nested rule blocks are never mistaken for one.
+/// The whole token around it becomes the dynamic value: in `margin: {y}px 0`,
+/// `{y}px` is replaced and `0` is kept. A `url(...)` containing interpolations
+/// is replaced as a whole. Quoted strings and comments are left untouched.
+///
+/// Interpolations in at-rule preludes, like `@media (max-width: {bp}px)`, can't
+/// be set through custom properties and are reported as an error.
+pub fn replace_interpolations(css: &str, scope: &str) -> Result<DynamicCss, CssError> {
+    let spans = find_interpolations(css)?;
+    let mut result = String::with_capacity(css.len());
+    let mut values = Vec::new();
+    let mut copied = 0;
+    let mut next = 0;
+
+    while next < spans.len() {
+        // Extend the first interpolation to the start of its token
+        let mut start = css[copied..spans[next].start]
+            .rfind(is_value_delimiter)
+            .map_or(copied, |i| copied + i + 1);
+        let in_url = css[copied..start].to_ascii_lowercase().ends_with("url(");
+        if in_url {
+            start -= "url(".len();
+        }
+
+        // Take in the token's text and any interpolations directly following it;
+        // a `url(...)` is taken up to its closing parenthesis
+        let mut format = css[start..spans[next].start].to_string();
+        let mut expressions = Vec::new();
+        let url_end = in_url.then(|| {
+            css[spans[next].end..]
+                .find(')')
+                .map_or(css.len(), |i| spans[next].end + i + 1)
+        });
+        let mut end;
+        loop {
+            let span = &spans[next];
+            format.push_str("{}");
+            expressions.push(css[span.start + 1..span.end - 1].trim().to_string());
+            next += 1;
+
+            end = match url_end {
+                Some(url_end) if next < spans.len() && spans[next].start < url_end => {
+                    spans[next].start
+                }
+                Some(url_end) => url_end,
+                None => css[span.end..]
+                    .find(is_value_delimiter)
+                    .map_or(css.len(), |i| span.end + i),
+            };
+            format.push_str(&css[span.end..end]);
+            if next == spans.len() || spans[next].start != end {
+                break;
+            }
+        }
+
+        let property = format!("--{}_{}", scope, values.len());
+        result.push_str(&css[copied..start]);
+        result.push_str(&format!("var({})", property));
+        values.push(DynamicValue {
+            property,
+            format,
+            expressions,
+        });
+        copied = end;
+    }
+    result.push_str(&css[copied..]);
+
+    Ok(DynamicCss {
+        css: result,
+        values,
+    })
+}
+
+/// Byte range of an interpolation, including its braces.
+struct Span {
+    start: usize,
+    end: usize,
+}
+
+/// Finds every interpolation in declaration values, in order, or the first one
+/// in an at-rule prelude.
+fn find_interpolations(css: &str) -> Result<Vec<Span>, CssError> {
+    let mut spans = Vec::new();
+    let mut in_value = false;
+    let mut in_prelude = false;
+    let mut quote = None;
+    let mut skip_to = 0;
+
+    for (i, ch) in css.char_indices() {
+        if i < skip_to {
+            continue;
+        }
+        if let Some(open) = quote {
+            if ch == open {
+                quote = None;
+            }
+            continue;
+        }
+
+        match ch {
+            '"' | '\'' => quote = Some(ch),
+            '/' if css[i..].starts_with("/*") => {
+                skip_to = css[i + 2..].find("*/").map_or(css.len(), |j| i + j + 4);
+            }
+            '@' if !in_value => in_prelude = true,
+            ':' => in_value = true,
+            ';' | '}' => {
+                in_value = false;
+                in_prelude = false;
+            }
+            '{' => {
+                let close = css[i..].find('}').map(|j| i + j);
+                let is_interpolation = in_value
+                    && close.is_some_and(|close| {
+                        let expr = css[i + 1..close].trim();
+                        !expr.is_empty() && !expr.contains(['{', ':', ';'])
+                    });
+
+                match close {
+                    Some(_) if is_interpolation && in_prelude => {
+                        return Err(error_at(css, i, CssErrorKind::InterpolationInPrelude));
+                    }
+                    Some(close) if is_interpolation => {
+                        spans.push(Span {
+                            start: i,
+                            end: close + 1,
+                        });
+                        skip_to = close + 1;
+                    }
+                    // A rule block
+                    _ => {
+                        in_value = false;
+                        in_prelude = false;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(spans)
+}
+
+/// Builds an error of `kind` at byte offset `at` of `css`.
+fn error_at(css: &str, at: usize, kind: CssErrorKind) -> CssError {
+    let before = &css[..at];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    CssError {
+        kind,
+        line: before.matches('\n').count() + 1,
+        column: before[line_start..].chars().count() + 1,
+    }
+}
+
+/// Characters that end a token in a CSS value.
+#[inline]
+fn is_value_delimiter(ch: char) -> bool {
+    ch.is_whitespace()
+        || matches!(
+            ch,
+            ',' | ';' | ':' | '(' | ')' | '/' | '*' | '!' | '{' | '}' | '"' | '\''
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(property: &str, format: &str, expressions: &[&str]) -> DynamicValue {
+        DynamicValue {
+            property: property.to_string(),
+            format: format.to_string(),
+            expressions: expressions.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_replaces_value_tokens() {
+        let dynamic = replace_interpolations(
+            ".bar { width: {width}px; margin: { gap * 2 }px 0; color: {theme.color()}; }",
+            "sc_d",
+        )
+        .unwrap();
+        assert_eq!(
+            dynamic.css,
+            ".bar { width: var(--sc_d_0); margin: var(--sc_d_1) 0; color: var(--sc_d_2); }"
+        );
+        assert_eq!(
+            dynamic.values,
+            vec![
+                value("--sc_d_0", "{}px", &["width"]),
+                value("--sc_d_1", "{}px", &["gap * 2"]),
+                value("--sc_d_2", "{}", &["theme.color()"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_adjacent_interpolations_share_a_property() {
+        let dynamic = replace_interpolations(
+            "transform: translate({x}px, {y}{unit}); border: 1px solid #{hex}",
+            "s",
+        )
+        .unwrap();
+        assert_eq!(
+            dynamic.css,
+            "transform: translate(var(--s_0), var(--s_1)); border: 1px solid var(--s_2)"
+        );
+        assert_eq!(dynamic.values[1], value("--s_1", "{}{}", &["y", "unit"]));
+        assert_eq!(dynamic.values[2], value("--s_2", "#{}", &["hex"]));
+    }
+
+    #[test]
+    fn test_blocks_and_strings_are_not_interpolations() {
+        let css = ".a:hover { color: red; }\n\
+                   .b { &:focus { width: {w}px; } content: \"{x}\"; }\n\
+                   @media (min-width: 600px) { .c { } }";
+        let dynamic = replace_interpolations(css, "s").unwrap();
+        assert_eq!(
+            dynamic.css,
+            ".a:hover { color: red; }\n\
+             .b { &:focus { width: var(--s_0); } content: \"{x}\"; }\n\
+             @media (min-width: 600px) { .c { } }"
+        );
+        assert_eq!(dynamic.values, vec![value("--s_0", "{}px", &["w"])]);
+    }
+
+    #[test]
+    fn test_comments_are_not_interpolations() {
+        let css = "/* usage: set {width} from props */ .a { width: {w}px; /* color: {c}; */ }";
+        let dynamic = replace_interpolations(css, "s").unwrap();
+        assert_eq!(
+            dynamic.css,
+            "/* usage: set {width} from props */ .a { width: var(--s_0); /* color: {c}; */ }"
+        );
+        assert_eq!(dynamic.values, vec![value("--s_0", "{}px", &["w"])]);
+
+        // An unterminated comment runs to the end
+        assert!(replace_interpolations(".a { /* width: {w}px; }", "s")
+            .unwrap()
+            .values
+            .is_empty());
+    }
+
+    #[test]
+    fn test_url_is_replaced_as_a_whole() {
+        let dynamic = replace_interpolations(
+            ".a { background: url({img}) no-repeat, URL({base}/icons/{name}.svg); }",
+            "s",
+        )
+        .unwrap();
+        assert_eq!(
+            dynamic.css,
+            ".a { background: var(--s_0) no-repeat, var(--s_1); }"
+        );
+        assert_eq!(
+            dynamic.values,
+            vec![
+                value("--s_0", "url({})", &["img"]),
+                value("--s_1", "URL({}/icons/{}.svg)", &["base", "name"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_interpolation_in_at_rule_prelude() {
+        let css = ".a { width: {w}px; }\n@media (max-width:{bp}px) { .a { width: 100%; } }";
+        let err = replace_interpolations(css, "s").unwrap_err();
+        assert_eq!(err.kind, CssErrorKind::InterpolationInPrelude);
+        assert_eq!((err.line, err.column), (2, 19));
+
+        // Blocks of at-rules still take interpolations
+        let css = "@media (min-width: 600px) { .a { width: {w}px; } }";
+        let dynamic = replace_interpolations(css, "s").unwrap();
+        assert_eq!(dynamic.values, vec![value("--s_0", "{}px", &["w"])]);
+    }
+}
@@ -0,0 +1,21 @@
+//! dioxus_style_core/src/lib.rs
+//! CSS scoping pipeline shared by the macros and the runtime
+
+pub mod css_error;
+pub mod dynamic;
+pub mod hash;
+pub mod paths;
+pub mod sass;
+pub mod source;
+pub mod style_parser;
+
+pub use css_error::{check_declarations, check_syntax, CssError, CssErrorKind};
+pub use dynamic::{replace_interpolations, DynamicCss, DynamicValue};
+pub use hash::generate_hash;
+pub use paths::{candidate_paths, is_likely_file_path, is_sass_path};
+pub use sass::{compile_sass, CompiledSass};
+pub use source::{load_sources, CssSource, LoadError, LoadedCss, LoadedFile};
+pub use style_parser::{
+    minify_css, parse_and_scope, parse_and_scope_with, wrap_declarations, CustomProperties,
+    Nesting, ScopeOptions, ScopedCss,
+};
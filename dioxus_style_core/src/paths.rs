@@ -0,0 +1,64 @@
+//! CSS file path handling shared by the macros and the build-time extractor.
+//!
+//! Both must resolve the same file from the same literal, since the path as
+//! written is part of the scope hash.
+
+use std::path::{Path, PathBuf};
+
+/// Checks if a style macro argument looks like a file path rather than inline CSS.
+#[inline]
+pub fn is_likely_file_path(s: &str) -> bool {
+    s.ends_with(".css") || is_sass_path(s) || s.contains('/') || s.contains('\\')
+}
+
+/// Checks if a file path refers to a Sass stylesheet (`.scss` or `.sass`).
+#[inline]
+pub fn is_sass_path(path: &str) -> bool {
+    let lower = path.to_ascii_lowercase();
+    lower.ends_with(".scss") || lower.ends_with(".sass")
+}
+
+/// Returns the locations a CSS file path may refer to, in lookup order.
+///
+/// Relative paths are anchored at `manifest_dir` (the crate being compiled),
+/// first as given and then under `src/`. Absolute paths are used as-is.
+pub fn candidate_paths(manifest_dir: &Path, file_path: &str) -> Vec<PathBuf> {
+    let path = Path::new(file_path);
+    if path.is_absolute() {
+        return vec![path.to_path_buf()];
+    }
+
+    vec![manifest_dir.join(path), manifest_dir.join("src").join(path)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_likely_file_path() {
+        assert!(is_likely_file_path("button.css"));
+        assert!(is_likely_file_path("styles/button.css"));
+        assert!(is_likely_file_path("./button.css"));
+        assert!(is_likely_file_path("button.scss"));
+        assert!(is_likely_file_path("button.sass"));
+
+        assert!(!is_likely_file_path(".button { color: red; }"));
+        assert!(!is_likely_file_path("color: red; font-size: 16px;"));
+    }
+
+    #[test]
+    fn test_candidate_paths_anchored_at_manifest_dir() {
+        let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let paths = candidate_paths(&manifest_dir, "styles/button.css");
+
+        assert_eq!(
+            paths,
+            vec![
+                manifest_dir.join("styles/button.css"),
+                manifest_dir.join("src/styles/button.css"),
+            ]
+        );
+        assert!(paths.iter().all(|p| p.is_absolute()));
+    }
+}
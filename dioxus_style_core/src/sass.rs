@@ -0,0 +1,101 @@
+//! Sass compilation.
+//!
+//! With the `scss` feature, `.scss` and `.sass` files are compiled to CSS with
+//! `grass` before scoping.
+//! Every file the compiler reads is recorded, so imported partials can be
+//! tracked for rebuilds and hot reloading.
+
+#[cfg(feature = "scss")]
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+
+/// A compiled Sass stylesheet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompiledSass {
+    /// The generated CSS.
+    pub css: String,
+    /// Files read through `@use`, `@forward` and `@import`, excluding the
+    /// compiled file itself.
+    pub imports: Vec<PathBuf>,
+}
+
+/// Compiles the Sass file at `path` to CSS.
+///
+/// Imports are resolved relative to the importing file. Errors are returned as
+/// the compiler's message, which includes the file and line.
+#[cfg(feature = "scss")]
+pub fn compile_sass(path: &Path) -> Result<CompiledSass, String> {
+    let fs = RecordingFs::default();
+    // `@warn` and `@debug` output is dropped rather than printed during builds
+    let options = grass::Options::default().fs(&fs).logger(&grass::NullLogger);
+    let css = grass::from_path(path, &options).map_err(|err| err.to_string())?;
+
+    let mut imports = fs.read.take();
+    imports.retain(|read| read != path);
+    imports.dedup();
+
+    Ok(CompiledSass { css, imports })
+}
+
+/// Fails: Sass support is not compiled in.
+#[cfg(not(feature = "scss"))]
+pub fn compile_sass(_path: &Path) -> Result<CompiledSass, String> {
+    Err("Sass files require the `scss` feature of dioxus_style".to_string())
+}
+
+/// File system that records every file read by the compiler.
+#[cfg(feature = "scss")]
+#[derive(Debug, Default)]
+struct RecordingFs {
+    read: RefCell<Vec<PathBuf>>,
+}
+
+#[cfg(feature = "scss")]
+impl grass::Fs for RecordingFs {
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        let content = std::fs::read(path)?;
+        self.read.borrow_mut().push(path.to_path_buf());
+        Ok(content)
+    }
+}
+
+#[cfg(all(test, feature = "scss"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compiles_with_partials() {
+        let dir = std::env::temp_dir().join("dioxus_style_core_sass");
+        std::fs::create_dir_all(dir.join("partials")).unwrap();
+        std::fs::write(dir.join("partials/_theme.scss"), "$accent: red;").unwrap();
+        std::fs::write(
+            dir.join("button.scss"),
+            "@use 'partials/theme';\n.button { color: theme.$accent; &:hover { color: blue; } }",
+        )
+        .unwrap();
+
+        let compiled = compile_sass(&dir.join("button.scss")).unwrap();
+        assert!(compiled.css.contains(".button {\n  color: red;\n}"));
+        assert!(compiled.css.contains(".button:hover"));
+        assert_eq!(compiled.imports.len(), 1);
+        assert!(compiled.imports[0].ends_with("_theme.scss"));
+    }
+
+    #[test]
+    fn test_sass_errors() {
+        let dir = std::env::temp_dir().join("dioxus_style_core_sass_errors");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("broken.scss"), ".a { color: $missing; }").unwrap();
+
+        let err = compile_sass(&dir.join("broken.scss")).unwrap_err();
+        assert!(err.contains("Undefined variable"), "{}", err);
+    }
+}
@@ -0,0 +1,288 @@
+//! Loading the stylesheets of one style macro invocation.
+//!
+//! A component may combine several sources, e.g. a shared base file and its own
+//! file. They are merged into one stylesheet under a single scope, hashed the
+//! same way by the macros and the build-time extractor.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::css_error::{check_syntax, CssError};
+use crate::hash::generate_hash;
+use crate::paths::{candidate_paths, is_likely_file_path, is_sass_path};
+use crate::sass::compile_sass;
+
+/// One stylesheet passed to a style macro.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CssSource {
+    /// A CSS file path as written in the macro call.
+    File(String),
+    /// Inline CSS.
+    Inline(String),
+}
+
+impl CssSource {
+    /// Treats `value` as a file path if it looks like one, otherwise as inline CSS.
+    pub fn detect(value: String) -> Self {
+        if is_likely_file_path(&value) {
+            CssSource::File(value)
+        } else {
+            CssSource::Inline(value)
+        }
+    }
+}
+
+/// A CSS file read while loading sources.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadedFile {
+    /// The path as written in the macro call.
+    pub path: String,
+    /// The location the file was read from.
+    pub resolved: PathBuf,
+    /// Partials imported by a Sass file, which affect its output too.
+    pub imports: Vec<PathBuf>,
+}
+
+/// The merged, unscoped stylesheet of a list of sources.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadedCss {
+    /// The scope id for the merged stylesheet.
+    pub scope: String,
+    /// The sources' CSS, in order, separated by newlines.
+    pub css: String,
+    /// The files that were read, in source order.
+    pub files: Vec<LoadedFile>,
+}
+
+/// Error loading the source at `index`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadError {
+    /// A CSS file was not found at any candidate location.
+    MissingFile {
+        index: usize,
+        path: String,
+        tried: Vec<PathBuf>,
+    },
+    /// A Sass file failed to compile, or Sass support is not enabled.
+    Sass {
+        index: usize,
+        path: String,
+        message: String,
+    },
+    /// A source is not valid CSS; `source` names it for error messages.
+    Css {
+        index: usize,
+        source: String,
+        error: CssError,
+    },
+}
+
+impl LoadError {
+    /// Index of the source that failed to load.
+    pub fn index(&self) -> usize {
+        match self {
+            LoadError::MissingFile { index, .. }
+            | LoadError::Sass { index, .. }
+            | LoadError::Css { index, .. } => *index,
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::MissingFile { path, tried, .. } => {
+                write!(f, "Failed to find CSS file '{}'. Tried paths:", path)?;
+                for candidate in tried {
+                    write!(f, "\n  - {}", candidate.display())?;
+                }
+                Ok(())
+            }
+            LoadError::Sass { path, message, .. } => {
+                write!(f, "failed to compile '{}': {}", path, message)
+            }
+            LoadError::Css { source, error, .. } => {
+                write!(f, "invalid CSS in {}", error.with_source(source))
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// Reads and syntax-checks `sources`, resolving files against `manifest_dir`,
+/// and merges them under one scope. Sass files are compiled to CSS first.
+///
+/// A single source is hashed exactly as before merging was supported: a file by
+/// its path and content, inline CSS by its content.
+pub fn load_sources(sources: &[CssSource], manifest_dir: &Path) -> Result<LoadedCss, LoadError> {
+    let mut parts = Vec::with_capacity(sources.len());
+    let mut files = Vec::new();
+
+    for (index, source) in sources.iter().enumerate() {
+        let (content, name) = match source {
+            CssSource::File(path) => {
+                let tried = candidate_paths(manifest_dir, path);
+                let Some((resolved, content)) = tried.iter().find_map(|candidate| {
+                    std::fs::read_to_string(candidate)
+                        .ok()
+                        .map(|content| (candidate.clone(), content))
+                }) else {
+                    return Err(LoadError::MissingFile {
+                        index,
+                        path: path.clone(),
+                        tried,
+                    });
+                };
+
+                let (content, imports) = if is_sass_path(path) {
+                    let compiled = compile_sass(&resolved).map_err(|message| LoadError::Sass {
+                        index,
+                        path: path.clone(),
+                        message,
+                    })?;
+                    (compiled.css, compiled.imports)
+                } else {
+                    (content, Vec::new())
+                };
+
+                files.push(LoadedFile {
+                    path: path.clone(),
+                    resolved,
+                    imports,
+                });
+                (content, path.as_str())
+            }
+            CssSource::Inline(css) => (css.clone(), "<inline>"),
+        };
+
+        check_syntax(&content).map_err(|error| LoadError::Css {
+            index,
+            source: name.to_string(),
+            error,
+        })?;
+        parts.push(content);
+    }
+
+    let css = parts.join("\n");
+    let file_key = files
+        .iter()
+        .map(|file| file.path.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+    let scope = generate_hash(&css, (!files.is_empty()).then_some(file_key.as_str()));
+
+    Ok(LoadedCss { scope, css, files })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("dioxus_style_core_{}", name));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            CssSource::detect("a.css".to_string()),
+            CssSource::File("a.css".to_string())
+        );
+        assert_eq!(
+            CssSource::detect(".a { }".to_string()),
+            CssSource::Inline(".a { }".to_string())
+        );
+    }
+
+    #[test]
+    fn test_single_source_hash_unchanged() {
+        let dir = temp_dir("single");
+        std::fs::write(dir.join("src/a.css"), ".a { color: red; }").unwrap();
+
+        let file = load_sources(&[CssSource::File("a.css".to_string())], &dir).unwrap();
+        assert_eq!(
+            file.scope,
+            generate_hash(".a { color: red; }", Some("a.css"))
+        );
+        assert_eq!(file.files[0].resolved, dir.join("src/a.css"));
+
+        let inline = load_sources(&[CssSource::Inline(".b { }".to_string())], &dir).unwrap();
+        assert_eq!(inline.scope, generate_hash(".b { }", None));
+        assert!(inline.files.is_empty());
+    }
+
+    #[test]
+    fn test_merges_sources() {
+        let dir = temp_dir("merge");
+        std::fs::write(dir.join("base.css"), ".base { margin: 0; }").unwrap();
+        std::fs::write(dir.join("button.css"), ".btn { color: red; }").unwrap();
+
+        let sources = [
+            CssSource::File("base.css".to_string()),
+            CssSource::File("button.css".to_string()),
+            CssSource::Inline(".extra { }".to_string()),
+        ];
+        let loaded = load_sources(&sources, &dir).unwrap();
+
+        assert_eq!(
+            loaded.css,
+            ".base { margin: 0; }\n.btn { color: red; }\n.extra { }"
+        );
+        assert_eq!(
+            loaded.scope,
+            generate_hash(&loaded.css, Some("base.css;button.css"))
+        );
+        assert_eq!(loaded.files.len(), 2);
+    }
+
+    #[test]
+    fn test_load_errors_name_the_source() {
+        let dir = temp_dir("errors");
+        std::fs::write(dir.join("bad.css"), ".a { color: red;").unwrap();
+
+        let missing = [
+            CssSource::Inline(".a { }".to_string()),
+            CssSource::File("missing.css".to_string()),
+        ];
+        let err = load_sources(&missing, &dir).unwrap_err();
+        assert_eq!(err.index(), 1);
+        assert!(err
+            .to_string()
+            .starts_with("Failed to find CSS file 'missing.css'"));
+
+        let invalid = [CssSource::File("bad.css".to_string())];
+        let err = load_sources(&invalid, &dir).unwrap_err();
+        assert_eq!(err.index(), 0);
+        assert_eq!(
+            err.to_string(),
+            "invalid CSS in bad.css:1:4: unclosed block: missing `}`"
+        );
+    }
+
+    #[test]
+    fn test_sass_sources() {
+        let dir = temp_dir("sass");
+        std::fs::write(dir.join("_vars.scss"), "$gap: 4px;").unwrap();
+        std::fs::write(
+            dir.join("card.scss"),
+            "@use 'vars';\n.card { margin: vars.$gap; }",
+        )
+        .unwrap();
+
+        let result = load_sources(&[CssSource::File("card.scss".to_string())], &dir);
+
+        #[cfg(feature = "scss")]
+        {
+            let loaded = result.unwrap();
+            assert_eq!(loaded.css, ".card {\n  margin: 4px;\n}\n");
+            assert_eq!(loaded.scope, generate_hash(&loaded.css, Some("card.scss")));
+            assert_eq!(loaded.files[0].imports, vec![dir.join("_vars.scss")]);
+        }
+
+        #[cfg(not(feature = "scss"))]
+        assert!(matches!(result, Err(LoadError::Sass { index: 0, .. })));
+    }
+}
@@ -0,0 +1,1594 @@
+//! CSS parsing and scoping utilities.
+//!
+//! Transforms CSS selectors by adding a unique scope prefix to prevent conflicts.
+
+use std::collections::{BTreeSet, HashSet};
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::css_error::{check_syntax, CssError};
+
+/// Represents parsed CSS with scoping applied.
+pub struct ScopedCss {
+    pub scoped: String,
+    /// Scoped class names (without the scope prefix), sorted.
+    pub class_names: Vec<String>,
+    /// Scoped ID names (without the scope prefix), sorted.
+    pub id_names: Vec<String>,
+}
+
+/// How nested style rules (`.card { .title { ... } &:hover { ... } }`) are emitted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Nesting {
+    /// Nested rules are flattened into top-level rules, with `&` replaced by the
+    /// parent selector and other selectors prefixed with it.
+    #[default]
+    Flatten,
+    /// Nested rules are kept in place as native CSS nesting, with their selectors
+    /// scoped. `&` is left for the browser to resolve.
+    Native,
+}
+
+/// Which custom properties (`--name`) are renamed to `--{scope}_name`, in their
+/// declarations and in `var()` references.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CustomProperties {
+    /// Custom properties are left untouched and inherited across components.
+    #[default]
+    Global,
+    /// Only private custom properties, named with a leading underscore
+    /// (`--_accent` becomes `--{scope}_accent`).
+    Private,
+    /// Private custom properties and every custom property declared in the
+    /// stylesheet. Properties that are only referenced, such as theme tokens
+    /// declared elsewhere, are left untouched.
+    Declared,
+}
+
+/// Options for [`parse_and_scope_with`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScopeOptions {
+    /// Minify the scoped CSS.
+    pub minify: bool,
+    /// How nested style rules are emitted.
+    pub nesting: Nesting,
+    /// Which custom properties are scoped.
+    pub custom_properties: CustomProperties,
+}
+
+impl ScopeOptions {
+    /// Options for the `native-nesting` and `scoped-custom-properties` features,
+    /// without minification.
+    ///
+    /// The macros, the build-time extractor and hot reloading all build their
+    /// options here, so the CSS they scope (and its hash) can't drift apart.
+    pub fn from_flags(native_nesting: bool, scoped_custom_properties: bool) -> Self {
+        Self {
+            minify: false,
+            nesting: if native_nesting {
+                Nesting::Native
+            } else {
+                Nesting::Flatten
+            },
+            custom_properties: if scoped_custom_properties {
+                CustomProperties::Declared
+            } else {
+                CustomProperties::Global
+            },
+        }
+    }
+}
+
+/// State shared by all rules while scoping a single stylesheet.
+struct ScopeContext<'a> {
+    scope: &'a str,
+    nesting: Nesting,
+    // Sorted sets keep generated code deterministic
+    class_names: BTreeSet<String>,
+    id_names: BTreeSet<String>,
+    // `@keyframes` names declared in this stylesheet
+    keyframes: HashSet<String>,
+    custom_properties: CustomProperties,
+    // Custom properties declared in this stylesheet, with their `--` prefix
+    declared_properties: HashSet<String>,
+}
+
+/// Parses and scopes CSS content with a unique prefix.
+///
+/// Nested style rules are flattened. Returns an error with the line and column of
+/// the first syntax error found.
+pub fn parse_and_scope(css: &str, scope: &str, minify: bool) -> Result<ScopedCss, CssError> {
+    parse_and_scope_with(
+        css,
+        scope,
+        &ScopeOptions {
+            minify,
+            ..ScopeOptions::default()
+        },
+    )
+}
+
+/// Parses and scopes CSS content with a unique prefix, as configured by `options`.
+///
+/// Returns an error with the line and column of the first syntax error found.
+pub fn parse_and_scope_with(
+    css: &str,
+    scope: &str,
+    options: &ScopeOptions,
+) -> Result<ScopedCss, CssError> {
+    check_syntax(css)?;
+    let minify = options.minify;
+
+    // Pre-allocate with estimate
+    let mut scoped_css = String::with_capacity(css.len() + scope.len() * 10);
+
+    let mut rules = parse_css_rules(css);
+    if options.nesting == Nesting::Flatten {
+        let mut flat = Vec::with_capacity(rules.len());
+        flatten_rules(&rules, &[], &mut flat);
+        rules = flat;
+    }
+
+    // Keyframes may be referenced before they are declared, so collect them first
+    let mut keyframes = HashSet::new();
+    collect_keyframes(&rules, &mut keyframes);
+
+    let mut declared_properties = HashSet::new();
+    if options.custom_properties == CustomProperties::Declared {
+        collect_custom_properties(&rules, &mut declared_properties);
+    }
+
+    let mut ctx = ScopeContext {
+        scope,
+        nesting: options.nesting,
+        class_names: BTreeSet::new(),
+        id_names: BTreeSet::new(),
+        keyframes,
+        custom_properties: options.custom_properties,
+        declared_properties,
+    };
+
+    for rule in rules {
+        if let Some(scoped_rule) = scope_rule(&rule, &mut ctx) {
+            scoped_css.push_str(&scoped_rule);
+            if !minify {
+                scoped_css.push('\n');
+            }
+        }
+    }
+
+    if minify {
+        scoped_css = minify_css(&scoped_css);
+    }
+
+    Ok(ScopedCss {
+        scoped: scoped_css,
+        class_names: ctx.class_names.into_iter().collect(),
+        id_names: ctx.id_names.into_iter().collect(),
+    })
+}
+
+/// Parses CSS into individual rules using a state machine approach.
+#[inline]
+fn parse_css_rules(css: &str) -> Vec<String> {
+    let mut rules = Vec::with_capacity(16);
+    let mut current_rule = String::with_capacity(128);
+    let mut brace_count = 0;
+    let mut chars = css.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        // Fast path: skip comments
+        if ch == '/' && chars.peek() == Some(&'*') {
+            chars.next(); // consume '*'
+                          // Skip until end of comment
+            while let Some(ch) = chars.next() {
+                if ch == '*' && chars.peek() == Some(&'/') {
+                    chars.next(); // consume '/'
+                    break;
+                }
+            }
+            continue;
+        }
+
+        current_rule.push(ch);
+
+        match ch {
+            // Copy strings verbatim so braces inside them don't count
+            '"' | '\'' => {
+                while let Some(next_ch) = chars.next() {
+                    current_rule.push(next_ch);
+                    if next_ch == '\\' {
+                        if let Some(escaped) = chars.next() {
+                            current_rule.push(escaped);
+                        }
+                    } else if next_ch == ch {
+                        break;
+                    }
+                }
+            }
+            '{' => brace_count += 1,
+            '}' => {
+                brace_count -= 1;
+
+                if brace_count == 0 {
+                    let trimmed = current_rule.trim();
+                    if !trimmed.is_empty() {
+                        rules.push(trimmed.to_string());
+                    }
+                    current_rule.clear();
+                }
+            }
+            // Statement at-rules (`@import ...;`) end at a top-level semicolon
+            ';' if brace_count == 0 => {
+                let trimmed = current_rule.trim();
+                if !trimmed.is_empty() {
+                    rules.push(trimmed.to_string());
+                }
+                current_rule.clear();
+            }
+            _ => {}
+        }
+    }
+
+    // Keep a trailing declaration without `;` (e.g. after a nested rule)
+    let trimmed = current_rule.trim();
+    if !trimmed.is_empty() {
+        rules.push(trimmed.to_string());
+    }
+
+    rules
+}
+
+/// Splits a block body into its declarations and its nested rules.
+fn split_nested(body: &str) -> (String, Vec<String>) {
+    if !body.contains('{') {
+        return (body.trim().to_string(), Vec::new());
+    }
+
+    let mut declarations = Vec::new();
+    let mut rules = Vec::new();
+    for item in parse_css_rules(body) {
+        if item.ends_with('}') {
+            rules.push(item);
+        } else {
+            declarations.push(item);
+        }
+    }
+
+    (declarations.join(" "), rules)
+}
+
+/// Flattens nested style rules into top-level rules.
+///
+/// `parents` are the resolved selectors of the enclosing style rule, if any.
+/// Rules without nested blocks are kept as they are.
+fn flatten_rules(rules: &[String], parents: &[String], out: &mut Vec<String>) {
+    for rule in rules {
+        let Some((prelude, body)) = split_block(rule) else {
+            out.push(rule.clone());
+            continue;
+        };
+
+        if prelude.starts_with('@') {
+            if !is_conditional_group_rule(&at_rule_name(prelude)) {
+                out.push(rule.clone());
+                continue;
+            }
+
+            // `.card { @media (...) { color: red; } }` applies to the parent selector
+            let (declarations, nested) = split_nested(body);
+            let mut inner = Vec::with_capacity(nested.len() + 1);
+            if !declarations.is_empty() && !parents.is_empty() {
+                inner.push(format!("{} {{ {} }}", parents.join(", "), declarations));
+            }
+            flatten_rules(&nested, parents, &mut inner);
+            out.push(format!("{} {{ {} }}", prelude, inner.join(" ")));
+            continue;
+        }
+
+        if parents.is_empty() && (!body.contains('{') || prelude == ":global") {
+            out.push(rule.clone());
+            continue;
+        }
+
+        let selectors = resolve_nested_selectors(prelude, parents);
+        let (declarations, nested) = split_nested(body);
+        if !declarations.is_empty() || nested.is_empty() {
+            out.push(format!("{} {{ {} }}", selectors.join(", "), declarations));
+        }
+        flatten_rules(&nested, &selectors, out);
+    }
+}
+
+/// Resolves the selector list of a nested rule against its parent selectors.
+///
+/// `&` is replaced by the parent; selectors without `&` become descendants of it
+/// (`> .child` becomes a child). Lists expand to every combination.
+fn resolve_nested_selectors(selector: &str, parents: &[String]) -> Vec<String> {
+    let list: Vec<&str> = split_selector_list(selector)
+        .into_iter()
+        .map(str::trim)
+        .collect();
+
+    if parents.is_empty() {
+        return list.into_iter().map(str::to_string).collect();
+    }
+
+    let mut resolved = Vec::with_capacity(parents.len() * list.len());
+    for parent in parents {
+        for part in &list {
+            resolved.push(
+                replace_nesting_selector(part, parent)
+                    .unwrap_or_else(|| format!("{} {}", parent, part)),
+            );
+        }
+    }
+    resolved
+}
+
+/// Replaces every `&` outside attribute selectors and strings with `parent`, or
+/// returns `None` if the selector has no `&`.
+fn replace_nesting_selector(selector: &str, parent: &str) -> Option<String> {
+    let mut result = String::with_capacity(selector.len() + parent.len());
+    let mut found = false;
+    let mut in_brackets = false;
+    let mut quote = None;
+
+    for ch in selector.chars() {
+        match (ch, quote) {
+            (c, Some(q)) if c == q => quote = None,
+            (_, Some(_)) => {}
+            ('"' | '\'', None) => quote = Some(ch),
+            ('[', None) => in_brackets = true,
+            (']', None) => in_brackets = false,
+            ('&', None) if !in_brackets => {
+                result.push_str(parent);
+                found = true;
+                continue;
+            }
+            _ => {}
+        }
+        result.push(ch);
+    }
+
+    found.then_some(result)
+}
+
+/// Splits a rule into its prelude (selector or at-rule header) and block body.
+#[inline]
+fn split_block(rule: &str) -> Option<(&str, &str)> {
+    // Find the opening brace
+    let brace_pos = rule.find('{')?;
+
+    let prelude = rule[..brace_pos].trim();
+    let rest = &rule[brace_pos + 1..];
+
+    // Remove closing brace
+    let body = if let Some(pos) = rest.rfind('}') {
+        rest[..pos].trim()
+    } else {
+        rest.trim()
+    };
+
+    Some((prelude, body))
+}
+
+/// Scopes a single CSS rule by prefixing selectors.
+#[inline]
+fn scope_rule(rule: &str, ctx: &mut ScopeContext) -> Option<String> {
+    let trimmed = rule.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    if trimmed.starts_with('@') {
+        return scope_at_rule(trimmed, ctx);
+    }
+
+    let (selector, declarations) = split_block(trimmed)?;
+
+    // `:global { ... }` emits its nested rules without scoping
+    if selector == ":global" {
+        let nested = parse_css_rules(declarations);
+        return Some(nested.join(" "));
+    }
+
+    let scoped_selector = scope_selector(selector, ctx);
+
+    // Native nesting: scope the nested rules in place
+    if ctx.nesting == Nesting::Native && declarations.contains('{') {
+        let (declarations, nested) = split_nested(declarations);
+        let body = scope_block(&declarations, &nested, ctx);
+        return Some(format!("{} {{ {} }}", scoped_selector, body));
+    }
+
+    let declarations = scope_declarations(declarations, ctx);
+
+    Some(format!("{} {{ {} }}", scoped_selector, declarations))
+}
+
+/// Scopes a block of declarations followed by nested rules.
+fn scope_block(declarations: &str, nested: &[String], ctx: &mut ScopeContext) -> String {
+    let mut parts = Vec::with_capacity(nested.len() + 1);
+    if !declarations.is_empty() {
+        parts.push(scope_declarations(declarations, ctx));
+    }
+    parts.extend(nested.iter().filter_map(|inner| scope_rule(inner, ctx)));
+    parts.join(" ")
+}
+
+/// Scopes an at-rule.
+///
+/// Conditional group rules (`@media`, `@supports`, `@container`, ...) keep their
+/// prelude verbatim and have every nested rule scoped recursively; the selectors
+/// in an `@scope` prelude are scoped as well. Other at-rules
+/// (`@font-face`, `@page`, `@import`, ...) are passed through unchanged.
+/// `@keyframes` names are prefixed with the scope; their frame selectors are kept.
+/// `@property` names are renamed like the custom properties they register.
+fn scope_at_rule(rule: &str, ctx: &mut ScopeContext) -> Option<String> {
+    // Statement at-rules such as `@import url(...);` have no block
+    let Some((prelude, body)) = split_block(rule) else {
+        return Some(rule.to_string());
+    };
+
+    let name = at_rule_name(prelude);
+
+    if is_keyframes_rule(&name) {
+        let keyframes_name = prelude[1 + name.len()..].trim();
+        return Some(format!(
+            "@{} {}_{} {{ {} }}",
+            name, ctx.scope, keyframes_name, body
+        ));
+    }
+
+    if name == "property" {
+        let prelude = rename_custom_properties(prelude, ctx);
+        return Some(format!("{} {{ {} }}", prelude, body));
+    }
+
+    if !is_conditional_group_rule(&name) {
+        return Some(format!("{} {{ {} }}", prelude, body));
+    }
+
+    // `@scope` limits are selectors of this stylesheet too
+    let prelude = if name == "scope" {
+        scope_scope_prelude(prelude, ctx)
+    } else {
+        prelude.to_string()
+    };
+
+    // Natively nested at-rules may hold the parent rule's declarations
+    if ctx.nesting == Nesting::Native {
+        let (declarations, nested) = split_nested(body);
+        let body = scope_block(&declarations, &nested, ctx);
+        return Some(format!("{} {{ {} }}", prelude, body));
+    }
+
+    let nested = parse_css_rules(body)
+        .iter()
+        .filter_map(|inner| scope_rule(inner, ctx))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Some(format!("{} {{ {} }}", prelude, nested))
+}
+
+/// Returns the lowercase name of an at-rule (`@media (...)` → `media`).
+#[inline]
+fn at_rule_name(prelude: &str) -> String {
+    prelude[1..]
+        .split(|c: char| c.is_whitespace() || c == '(')
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+/// Checks if an at-rule introduces a block of nested style rules.
+#[inline]
+fn is_conditional_group_rule(name: &str) -> bool {
+    matches!(
+        name,
+        "media"
+            | "supports"
+            | "container"
+            | "layer"
+            | "scope"
+            | "starting-style"
+            | "document"
+            | "-moz-document"
+    )
+}
+
+/// Scopes the `(...)` selector lists of an `@scope (.root) to (.limit)` prelude.
+fn scope_scope_prelude(prelude: &str, ctx: &mut ScopeContext) -> String {
+    let mut scoped = String::with_capacity(prelude.len() + 32);
+    let mut chars = prelude.chars().peekable();
+
+    while let Some(&ch) = chars.peek() {
+        if ch == '(' {
+            let selector = take_parenthesized(&mut chars);
+            scoped.push('(');
+            scoped.push_str(&scope_selector(selector.trim(), ctx));
+            scoped.push(')');
+        } else {
+            scoped.push(ch);
+            chars.next();
+        }
+    }
+
+    scoped
+}
+
+/// Checks if an at-rule declares keyframes (including vendor-prefixed forms).
+#[inline]
+fn is_keyframes_rule(name: &str) -> bool {
+    name == "keyframes" || (name.starts_with('-') && name.ends_with("-keyframes"))
+}
+
+/// Collects every `@keyframes` name declared in the given rules.
+fn collect_keyframes(rules: &[String], keyframes: &mut HashSet<String>) {
+    for rule in rules {
+        if !rule.starts_with('@') {
+            continue;
+        }
+
+        let Some((prelude, body)) = split_block(rule) else {
+            continue;
+        };
+        let name = at_rule_name(prelude);
+
+        if is_keyframes_rule(&name) {
+            keyframes.insert(prelude[1 + name.len()..].trim().to_string());
+        } else if is_conditional_group_rule(&name) {
+            collect_keyframes(&parse_css_rules(body), keyframes);
+        }
+    }
+}
+
+/// Rewrites references to scoped keyframes in `animation` / `animation-name`,
+/// and renames scoped custom properties.
+fn scope_declarations(declarations: &str, ctx: &ScopeContext) -> String {
+    if ctx.keyframes.is_empty() && ctx.custom_properties == CustomProperties::Global {
+        return declarations.to_string();
+    }
+
+    declarations
+        .split(';')
+        .map(|declaration| {
+            let Some(colon) = declaration.find(':') else {
+                return declaration.to_string();
+            };
+
+            let (name, value) = (&declaration[..colon], &declaration[colon + 1..]);
+            let property = name.trim().to_ascii_lowercase();
+            let unprefixed = property.strip_prefix("-webkit-").unwrap_or(&property);
+            let value = if matches!(unprefixed, "animation" | "animation-name") {
+                rename_identifiers(value, &ctx.keyframes, ctx.scope)
+            } else {
+                value.to_string()
+            };
+
+            format!(
+                "{}:{}",
+                rename_custom_properties(name, ctx),
+                rename_custom_properties(&value, ctx)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Collects every custom property declared in the given rules, including
+/// `@property` registrations. Rules inside `:global` blocks are skipped.
+fn collect_custom_properties(rules: &[String], names: &mut HashSet<String>) {
+    for rule in rules {
+        let Some((prelude, body)) = split_block(rule.trim()) else {
+            continue;
+        };
+
+        if prelude.starts_with('@') {
+            let name = at_rule_name(prelude);
+            if name == "property" {
+                names.insert(prelude[1 + name.len()..].trim().to_string());
+            } else if is_conditional_group_rule(&name) {
+                collect_custom_properties(&parse_css_rules(body), names);
+            }
+            continue;
+        }
+        if prelude == ":global" {
+            continue;
+        }
+
+        // A declaration starts a block or follows another one: `{ --name: ...`
+        let mut start = 0;
+        while let Some(found) = body[start..].find("--") {
+            let at = start + found;
+            let len = body[at..]
+                .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+                .unwrap_or(body.len() - at);
+            start = at + len;
+
+            let before = body[..at].trim_end();
+            let is_declaration = (before.is_empty() || before.ends_with(['{', ';']))
+                && body[start..].trim_start().starts_with(':');
+            if is_declaration {
+                names.insert(body[at..start].to_string());
+            }
+        }
+    }
+}
+
+/// Renames the scoped custom properties in a declaration name or value, outside
+/// of quoted strings.
+fn rename_custom_properties(text: &str, ctx: &ScopeContext) -> String {
+    if ctx.custom_properties == CustomProperties::Global || !text.contains("--") {
+        return text.to_string();
+    }
+
+    let mut result = String::with_capacity(text.len() + ctx.scope.len());
+    let mut ident = String::with_capacity(16);
+    let mut quote = None;
+
+    let flush = |ident: &mut String, result: &mut String| {
+        match scoped_property_suffix(ident, ctx) {
+            Some(suffix) => {
+                result.push_str("--");
+                result.push_str(ctx.scope);
+                result.push('_');
+                result.push_str(suffix);
+            }
+            None => result.push_str(ident),
+        }
+        ident.clear();
+    };
+
+    for ch in text.chars() {
+        if let Some(open) = quote {
+            if ch == open {
+                quote = None;
+            }
+            result.push(ch);
+        } else if ch.is_alphanumeric() || ch == '-' || ch == '_' {
+            ident.push(ch);
+        } else {
+            flush(&mut ident, &mut result);
+            if ch == '"' || ch == '\'' {
+                quote = Some(ch);
+            }
+            result.push(ch);
+        }
+    }
+    flush(&mut ident, &mut result);
+
+    result
+}
+
+/// Returns the name a scoped custom property gets after the scope prefix
+/// (`--_accent` → `accent`), or `None` if `ident` is not scoped.
+fn scoped_property_suffix<'a>(ident: &'a str, ctx: &ScopeContext) -> Option<&'a str> {
+    let name = ident.strip_prefix("--")?;
+    if let Some(private) = name.strip_prefix('_') {
+        return (!private.is_empty()).then_some(private);
+    }
+
+    (ctx.custom_properties == CustomProperties::Declared && ctx.declared_properties.contains(ident))
+        .then_some(name)
+}
+
+/// Prefixes every identifier in `value` that is contained in `names`.
+fn rename_identifiers(value: &str, names: &HashSet<String>, scope: &str) -> String {
+    let mut result = String::with_capacity(value.len() + scope.len());
+    let mut ident = String::with_capacity(16);
+
+    let flush = |ident: &mut String, result: &mut String| {
+        if names.contains(ident.as_str()) {
+            result.push_str(scope);
+            result.push('_');
+        }
+        result.push_str(ident);
+        ident.clear();
+    };
+
+    for ch in value.chars() {
+        if ch.is_alphanumeric() || ch == '-' || ch == '_' {
+            ident.push(ch);
+        } else {
+            flush(&mut ident, &mut result);
+            result.push(ch);
+        }
+    }
+    flush(&mut ident, &mut result);
+
+    result
+}
+
+/// Scopes a CSS selector by adding the unique prefix.
+#[inline]
+fn scope_selector(selector: &str, ctx: &mut ScopeContext) -> String {
+    if !selector.contains(',') {
+        // Fast path: single selector
+        return scope_single_selector(selector, ctx);
+    }
+
+    // Handle multiple selectors (comma-separated)
+    split_selector_list(selector)
+        .into_iter()
+        .map(|s| scope_single_selector(s.trim(), ctx))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Splits a selector list on top-level commas, ignoring commas inside `(...)` and `[...]`.
+#[inline]
+fn split_selector_list(selector: &str) -> Vec<&str> {
+    let mut parts = Vec::with_capacity(4);
+    let mut depth = 0i32;
+    let mut start = 0;
+
+    for (i, ch) in selector.char_indices() {
+        match ch {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&selector[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&selector[start..]);
+
+    parts
+}
+
+/// Scopes the arguments of a functional pseudo-class.
+///
+/// Selector-list arguments (`:not()`, `:is()`, `:where()`, `:has()`) are scoped like
+/// top-level selectors, as is the `of S` part of `:nth-child()` / `:nth-last-child()`.
+/// Anything else (`:lang(en)`, `:nth-child(2n+1)`, ...) is returned unchanged.
+fn scope_pseudo_args(name: &str, args: &str, ctx: &mut ScopeContext) -> String {
+    match name {
+        "not" | "is" | "where" | "has" | "matches" | "-webkit-any" | "-moz-any" => {
+            scope_selector(args.trim(), ctx)
+        }
+        "nth-child" | "nth-last-child" => {
+            let lower = args.to_ascii_lowercase();
+            match lower.find(" of ") {
+                Some(pos) => format!(
+                    "{} of {}",
+                    args[..pos].trim(),
+                    scope_selector(args[pos + 4..].trim(), ctx)
+                ),
+                None => args.to_string(),
+            }
+        }
+        _ => args.to_string(),
+    }
+}
+
+/// Scopes a single selector (no commas).
+/// Scopes: classes (.class), IDs (#id), elements (div, span, etc.), the universal
+/// selector (*) and a leading `:root` / `:host`, which select the component's root
+/// elements. Elsewhere in a selector they keep their usual meaning.
+///
+/// `:global(...)` emits its argument unscoped and a bare `:global` leaves the rest
+/// of the selector unscoped; `:local(...)` / `:local` switch back to scoping.
+#[inline]
+fn scope_single_selector(selector: &str, ctx: &mut ScopeContext) -> String {
+    let scope = ctx.scope;
+    let mut result = String::with_capacity(selector.len() + scope.len() * 4);
+    let mut chars = selector.chars().peekable();
+    let mut at_start = true; // Track if we're at the start of a selector component
+    let mut global = false; // Inside a bare `:global` section
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            // Handle class selectors: .class → .scope.class
+            '.' => {
+                let class_name = take_identifier(&mut chars);
+
+                if !class_name.is_empty() && global {
+                    result.push('.');
+                    result.push_str(&class_name);
+                } else if !class_name.is_empty() {
+                    ctx.class_names.insert(class_name.clone());
+                    result.push('.');
+                    result.push_str(scope);
+                    result.push('_');
+                    result.push_str(&class_name);
+                }
+                at_start = false;
+            }
+
+            // Handle ID selectors: #id → #scope_id
+            '#' => {
+                let id_name = take_identifier(&mut chars);
+
+                if !id_name.is_empty() && global {
+                    result.push('#');
+                    result.push_str(&id_name);
+                } else if !id_name.is_empty() {
+                    ctx.id_names.insert(id_name.clone());
+                    result.push('#');
+                    result.push_str(scope);
+                    result.push('_');
+                    result.push_str(&id_name);
+                }
+                at_start = false;
+            }
+
+            // Handle combinators: reset the "at_start" flag
+            ' ' | '>' | '+' | '~' => {
+                result.push(ch);
+                // Skip extra whitespace
+                while let Some(&next_ch) = chars.peek() {
+                    if next_ch == ' ' {
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                at_start = true; // Next token is a new selector component
+            }
+
+            // Handle pseudo-classes and pseudo-elements
+            ':' => {
+                let is_element = chars.peek() == Some(&':');
+                if is_element {
+                    chars.next();
+                }
+
+                let name = take_identifier(&mut chars);
+                let args = if chars.peek() == Some(&'(') {
+                    Some(take_parenthesized(&mut chars))
+                } else {
+                    None
+                };
+
+                match (is_element, name.to_ascii_lowercase().as_str(), args) {
+                    // :global(.foo) → .foo
+                    (false, "global", Some(inner)) => result.push_str(inner.trim()),
+                    // :local(.foo) → .scope_foo
+                    (false, "local", Some(inner)) => {
+                        result.push_str(&scope_selector(inner.trim(), ctx));
+                    }
+                    // :root / :host → the component's root elements
+                    (false, "root" | "host", None) if !global && result.is_empty() => {
+                        result.push_str(&root_selector(scope));
+                    }
+                    // :host(.active) → root elements matching `.active`
+                    (false, "host", Some(inner)) if !global && result.is_empty() => {
+                        result.push_str(&root_selector(scope));
+                        result.push_str(":is(");
+                        result.push_str(&scope_selector(inner.trim(), ctx));
+                        result.push(')');
+                    }
+                    // Bare :global / :local switch scoping for the rest of the selector
+                    (false, "global", None) | (false, "local", None) => {
+                        global = name.eq_ignore_ascii_case("global");
+                        while chars.peek() == Some(&' ') {
+                            chars.next();
+                        }
+                        at_start = true;
+                        continue;
+                    }
+                    // Functional pseudo-classes may contain selectors that need scoping
+                    (false, lower, Some(args)) if !global => {
+                        let scoped_args = scope_pseudo_args(lower, &args, ctx);
+                        result.push(':');
+                        result.push_str(&name);
+                        result.push('(');
+                        result.push_str(&scoped_args);
+                        result.push(')');
+                    }
+                    // Other pseudo-classes and pseudo-elements pass through
+                    (_, _, args) => {
+                        result.push_str(if is_element { "::" } else { ":" });
+                        result.push_str(&name);
+                        if let Some(args) = args {
+                            result.push('(');
+                            result.push_str(&args);
+                            result.push(')');
+                        }
+                    }
+                }
+                at_start = false;
+            }
+
+            // Handle attribute selectors (pass through)
+            '[' => {
+                result.push(ch);
+                // Copy everything until closing bracket
+                for next_ch in chars.by_ref() {
+                    result.push(next_ch);
+                    if next_ch == ']' {
+                        break;
+                    }
+                }
+                at_start = false;
+            }
+
+            // Handle element selectors: div → div[data-scope="scope"]
+            ch if ch.is_alphabetic() && at_start && !global => {
+                let mut element_name = String::from(ch);
+
+                // Collect full element name
+                while let Some(&next_ch) = chars.peek() {
+                    if next_ch.is_alphanumeric() || next_ch == '-' {
+                        element_name.push(next_ch);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+
+                // Scope the element with data attribute
+                result.push_str(&element_name);
+                result.push_str("[data-scope=\"");
+                result.push_str(scope);
+                result.push_str("\"]");
+
+                at_start = false;
+            }
+
+            // Handle the universal selector: * → [data-scope="scope"]
+            '*' if at_start && !global => {
+                result.push_str("[data-scope=\"");
+                result.push_str(scope);
+                result.push_str("\"]");
+                at_start = false;
+            }
+
+            // Default: pass through
+            _ => {
+                result.push(ch);
+                at_start = false;
+            }
+        }
+    }
+
+    result
+}
+
+/// Selects the root elements of a component: elements carrying its scope that are
+/// not inside another of its elements.
+#[inline]
+fn root_selector(scope: &str) -> String {
+    format!("[data-scope=\"{0}\"]:not([data-scope=\"{0}\"] *)", scope)
+}
+
+/// Consumes an identifier (letters, digits, `-` and `_`).
+#[inline]
+fn take_identifier(chars: &mut Peekable<Chars>) -> String {
+    let mut ident = String::with_capacity(16);
+    while let Some(&next_ch) = chars.peek() {
+        if next_ch.is_alphanumeric() || next_ch == '-' || next_ch == '_' {
+            ident.push(next_ch);
+            chars.next();
+        } else {
+            break;
+        }
+    }
+    ident
+}
+
+/// Consumes a balanced `( ... )` group and returns its contents without the parens.
+fn take_parenthesized(chars: &mut Peekable<Chars>) -> String {
+    let mut inner = String::with_capacity(32);
+    let mut depth = 0;
+
+    for ch in chars.by_ref() {
+        match ch {
+            '(' => {
+                depth += 1;
+                if depth == 1 {
+                    continue;
+                }
+            }
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            _ => {}
+        }
+        inner.push(ch);
+    }
+
+    inner
+}
+
+/// Wraps the declarations of a `css!` invocation in a rule for the scope class.
+///
+/// The wrapper class is already the scope, so the result is only minified.
+#[inline]
+pub fn wrap_declarations(css: &str, scope: &str, minify: bool) -> String {
+    let wrapped = format!(".{} {{ {} }}", scope, css);
+    if minify {
+        minify_css(&wrapped)
+    } else {
+        wrapped
+    }
+}
+
+/// Minifies CSS by removing whitespace and comments.
+#[inline]
+pub fn minify_css(css: &str) -> String {
+    let mut result = String::with_capacity(css.len() / 2);
+    let mut chars = css.chars().peekable();
+    let mut last_was_space = false;
+
+    while let Some(ch) = chars.next() {
+        // Skip comments
+        if ch == '/' && chars.peek() == Some(&'*') {
+            chars.next();
+            while let Some(ch) = chars.next() {
+                if ch == '*' && chars.peek() == Some(&'/') {
+                    chars.next();
+                    break;
+                }
+            }
+            continue;
+        }
+
+        if ch.is_whitespace() {
+            if !last_was_space && !result.is_empty() {
+                if let Some(last_ch) = result.chars().last() {
+                    if !matches!(last_ch, '{' | '}' | ':' | ';' | ',') {
+                        result.push(' ');
+                        last_was_space = true;
+                    }
+                }
+            }
+        } else {
+            result.push(ch);
+            last_was_space = false;
+        }
+    }
+
+    result.shrink_to_fit();
+    result
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_class_selector_scoping() {
+        let css = ".button { color: red; }";
+        let scoped = parse_and_scope(css, "sc_abc", false).unwrap();
+        // v0.2.0: Changed from .sc_abc.button to .sc_abc_button
+        assert!(scoped.scoped.contains(".sc_abc_button"));
+    }
+
+    #[test]
+    fn test_id_selector_scoping() {
+        let css = "#header { color: blue; }";
+        let scoped = parse_and_scope(css, "sc_abc", false).unwrap();
+        assert!(scoped.scoped.contains("#sc_abc_header"));
+    }
+
+    #[test]
+    fn test_element_selector_scoping() {
+        let css = "div { margin: 10px; }";
+        let scoped = parse_and_scope(css, "sc_abc", false).unwrap();
+        assert!(scoped.scoped.contains("div[data-scope=\"sc_abc\"]"));
+    }
+
+    #[test]
+    fn test_complex_selector_mixed() {
+        let css = "div.container > .item + #special { color: green; }";
+        let scoped = parse_and_scope(css, "sc_xyz", false).unwrap();
+
+        // v0.2.0: Updated format for all selector types
+        assert!(scoped
+            .scoped
+            .contains("div[data-scope=\"sc_xyz\"].sc_xyz_container"));
+        assert!(scoped.scoped.contains(".sc_xyz_item"));
+        assert!(scoped.scoped.contains("#sc_xyz_special"));
+    }
+
+    #[test]
+    fn test_pseudo_classes() {
+        let css = ".button:hover { background: blue; }";
+        let scoped = parse_and_scope(css, "sc_abc", false).unwrap();
+        // v0.2.0: Changed format
+        assert!(scoped.scoped.contains(".sc_abc_button:hover"));
+    }
+
+    #[test]
+    fn test_multiple_selectors() {
+        let css = ".btn, .button, #submit { color: red; }";
+        let scoped = parse_and_scope(css, "sc_xyz", false).unwrap();
+
+        // v0.2.0: Changed format
+        assert!(scoped.scoped.contains(".sc_xyz_btn"));
+        assert!(scoped.scoped.contains(".sc_xyz_button"));
+        assert!(scoped.scoped.contains("#sc_xyz_submit"));
+    }
+
+    #[test]
+    fn test_no_extra_braces() {
+        let css = ".box { color: red; }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+
+        let open_count = scoped.scoped.matches('{').count();
+        let close_count = scoped.scoped.matches('}').count();
+
+        assert_eq!(open_count, close_count);
+    }
+
+    #[test]
+    fn test_minify() {
+        let css = r#"
+            .button {
+                color: red;
+            }
+        "#;
+
+        let scoped = parse_and_scope(css, "sc_test", true).unwrap();
+        assert!(scoped.scoped.len() < css.len());
+    }
+
+    #[test]
+    fn test_comments_removed() {
+        let css = r#"
+            /* Comment */
+            .button {
+                color: red; /* inline */
+            }
+        "#;
+
+        let scoped = parse_and_scope(css, "sc_test", true).unwrap();
+
+        assert!(!scoped.scoped.contains("/*"));
+        assert!(!scoped.scoped.contains("*/"));
+    }
+
+    #[test]
+    fn test_attribute_selectors() {
+        let css = "input[type=\"text\"] { border: 1px solid; }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped
+            .scoped
+            .contains("input[data-scope=\"sc_test\"][type=\"text\"]"));
+    }
+
+    #[test]
+    fn test_descendant_combinator() {
+        let css = ".parent .child { color: blue; }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        // v0.2.0: Changed format
+        assert!(scoped.scoped.contains(".sc_test_parent .sc_test_child"));
+    }
+
+    // Additional tests for v0.2.0 features
+
+    #[test]
+    fn test_element_with_class() {
+        let css = "div.container { padding: 20px; }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped
+            .scoped
+            .contains("div[data-scope=\"sc_test\"].sc_test_container"));
+    }
+
+    #[test]
+    fn test_element_with_id() {
+        let css = "section#main { margin: auto; }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped
+            .scoped
+            .contains("section[data-scope=\"sc_test\"]#sc_test_main"));
+    }
+
+    #[test]
+    fn test_multiple_elements() {
+        let css = "div, span, p { margin: 0; }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped.scoped.contains("div[data-scope=\"sc_test\"]"));
+        assert!(scoped.scoped.contains("span[data-scope=\"sc_test\"]"));
+        assert!(scoped.scoped.contains("p[data-scope=\"sc_test\"]"));
+    }
+
+    #[test]
+    fn test_pseudo_element() {
+        let css = ".button::before { content: '→'; }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped.scoped.contains(".sc_test_button::before"));
+    }
+
+    #[test]
+    fn test_multiple_pseudo_classes() {
+        let css = ".link:hover:focus { text-decoration: underline; }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped.scoped.contains(".sc_test_link:hover:focus"));
+    }
+
+    #[test]
+    fn test_deeply_nested_selector() {
+        let css = ".nav > ul > li > a.active { color: blue; }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped.scoped.contains(".sc_test_nav"));
+        assert!(scoped.scoped.contains("ul[data-scope=\"sc_test\"]"));
+        assert!(scoped.scoped.contains("li[data-scope=\"sc_test\"]"));
+        assert!(scoped
+            .scoped
+            .contains("a[data-scope=\"sc_test\"].sc_test_active"));
+    }
+
+    #[test]
+    fn test_empty_css() {
+        let css = "";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert_eq!(scoped.scoped, "");
+    }
+
+    #[test]
+    fn test_whitespace_only() {
+        let css = "   \n\t   ";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert_eq!(scoped.scoped.trim(), "");
+    }
+
+    #[test]
+    fn test_media_query_nested_rules() {
+        let css = "@media (max-width: 600px) { .a { color: red; } div { margin: 0; } }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped.scoped.starts_with("@media (max-width: 600px) {"));
+        assert!(scoped.scoped.contains(".sc_test_a { color: red; }"));
+        assert!(scoped
+            .scoped
+            .contains("div[data-scope=\"sc_test\"] { margin: 0; }"));
+    }
+
+    #[test]
+    fn test_nested_conditional_groups() {
+        let css = "@supports (display: grid) { @container card (min-width: 20em) { .grid { display: grid; } } }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped.scoped.contains("@supports (display: grid) {"));
+        assert!(scoped
+            .scoped
+            .contains("@container card (min-width: 20em) {"));
+        assert!(scoped.scoped.contains(".sc_test_grid { display: grid; }"));
+        assert_eq!(
+            scoped.scoped.matches('{').count(),
+            scoped.scoped.matches('}').count()
+        );
+    }
+
+    #[test]
+    fn test_scope_and_starting_style_rules() {
+        let css = "@scope (.card) to (.content) { .x { color: red; } } @starting-style { .x { opacity: 0; } }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped
+            .scoped
+            .contains("@scope (.sc_test_card) to (.sc_test_content) {"));
+        assert!(scoped.scoped.contains(".sc_test_x { color: red; }"));
+        assert!(scoped.scoped.contains("@starting-style {"));
+        assert!(scoped.scoped.contains(".sc_test_x { opacity: 0; }"));
+        assert!(!scoped.scoped.contains(" .x "));
+    }
+
+    #[test]
+    fn test_non_group_at_rules_pass_through() {
+        let css = "@import url(\"base.css\"); @font-face { font-family: Foo; src: url(foo.woff); } .a { color: red; }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped.scoped.contains("@import url(\"base.css\");"));
+        assert!(scoped
+            .scoped
+            .contains("@font-face { font-family: Foo; src: url(foo.woff); }"));
+        assert!(scoped.scoped.contains(".sc_test_a { color: red; }"));
+    }
+
+    #[test]
+    fn test_media_query_minified() {
+        let css = "@media screen and (max-width: 600px) {\n  .a { color: red; }\n}";
+        let scoped = parse_and_scope(css, "sc_test", true).unwrap();
+        assert!(scoped
+            .scoped
+            .starts_with("@media screen and (max-width:600px)"));
+        assert!(scoped.scoped.contains(".sc_test_a {color:red;}"));
+    }
+
+    #[test]
+    fn test_keyframes_scoped() {
+        let css = "@keyframes spin { from { transform: rotate(0deg); } 50% { opacity: 0.5; } to { transform: rotate(360deg); } }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped.scoped.contains("@keyframes sc_test_spin {"));
+        assert!(scoped.scoped.contains("from { transform: rotate(0deg); }"));
+        assert!(scoped.scoped.contains("50% { opacity: 0.5; }"));
+        assert!(scoped.scoped.contains("to { transform: rotate(360deg); }"));
+    }
+
+    #[test]
+    fn test_animation_references_rewritten() {
+        let css = r#"
+            .loader { animation: spin 1s linear infinite, fade 2s; }
+            .icon { animation-name: spin; }
+            .other { animation: unknown 1s; }
+            @keyframes spin { to { transform: rotate(360deg); } }
+            @-webkit-keyframes fade { to { opacity: 0; } }
+        "#;
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped
+            .scoped
+            .contains("animation: sc_test_spin 1s linear infinite, sc_test_fade 2s;"));
+        assert!(scoped.scoped.contains("animation-name: sc_test_spin;"));
+        assert!(scoped.scoped.contains("animation: unknown 1s;"));
+        assert!(scoped.scoped.contains("@-webkit-keyframes sc_test_fade {"));
+    }
+
+    #[test]
+    fn test_keyframes_inside_media_query() {
+        let css = "@media (prefers-reduced-motion: no-preference) { @keyframes pulse { to { opacity: 0; } } .dot { animation: pulse 1s; } }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped.scoped.contains("@keyframes sc_test_pulse {"));
+        assert!(scoped
+            .scoped
+            .contains(".sc_test_dot { animation: sc_test_pulse 1s; }"));
+    }
+
+    #[test]
+    fn test_global_function() {
+        let css = ".card :global(.tooltip) { z-index: 10; }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped.scoped.contains(".sc_test_card .tooltip {"));
+        assert!(!scoped.class_names.contains(&"tooltip".to_string()));
+    }
+
+    #[test]
+    fn test_global_element() {
+        let css = ":global(body) .app { margin: 0; }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped.scoped.contains("body .sc_test_app {"));
+    }
+
+    #[test]
+    fn test_bare_global_switches_mode() {
+        let css = ".wrapper :global .widget span { color: red; }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped.scoped.contains(".sc_test_wrapper .widget span {"));
+    }
+
+    #[test]
+    fn test_local_inside_global() {
+        let css = ":global .theme-dark :local(.button) { color: white; }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped.scoped.contains(".theme-dark .sc_test_button {"));
+    }
+
+    #[test]
+    fn test_global_block() {
+        let css = ":global { .tooltip { color: red; } body { margin: 0; } } .a { color: blue; }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped.scoped.contains(".tooltip { color: red; }"));
+        assert!(scoped.scoped.contains("body { margin: 0; }"));
+        assert!(scoped.scoped.contains(".sc_test_a { color: blue; }"));
+    }
+
+    #[test]
+    fn test_not_pseudo_class_scoped() {
+        let css = ".card:not(.active) { opacity: 0.5; }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped
+            .scoped
+            .contains(".sc_test_card:not(.sc_test_active) {"));
+        assert!(scoped.class_names.contains(&"active".to_string()));
+    }
+
+    #[test]
+    fn test_selector_list_pseudo_classes() {
+        let css = ".list :is(.item, #first) :where(span, .label), .x:has(> img) { color: red; }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped.scoped.contains(":is(.sc_test_item, #sc_test_first)"));
+        assert!(scoped
+            .scoped
+            .contains(":where(span[data-scope=\"sc_test\"], .sc_test_label)"));
+        assert!(scoped
+            .scoped
+            .contains(".sc_test_x:has(>img[data-scope=\"sc_test\"])"));
+    }
+
+    #[test]
+    fn test_nested_functional_pseudo_classes() {
+        let css = ".a:not(:is(.b, .c)) { color: red; }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped
+            .scoped
+            .contains(".sc_test_a:not(:is(.sc_test_b, .sc_test_c)) {"));
+    }
+
+    #[test]
+    fn test_nth_child_of_selector() {
+        let css = "li:nth-child(2n+1 of .x), li:nth-child(odd) { color: red; }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped.scoped.contains(":nth-child(2n+1 of .sc_test_x)"));
+        assert!(scoped.scoped.contains(":nth-child(odd)"));
+    }
+
+    #[test]
+    fn test_part_pseudo_element_untouched() {
+        let css = ".host::part(label) { color: red; }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert!(scoped.scoped.contains(".sc_test_host::part(label) {"));
+    }
+
+    #[test]
+    fn test_class_and_id_names_collected() {
+        let css = ".b, .a #main :global(.ext) { color: red; } .a:not(.c) { color: blue; }";
+        let scoped = parse_and_scope(css, "sc_test", false).unwrap();
+        assert_eq!(scoped.class_names, vec!["a", "b", "c"]);
+        assert_eq!(scoped.id_names, vec!["main"]);
+    }
+
+    #[test]
+    fn test_nested_rules_flattened() {
+        let css = ".card { color: red; .title { margin: 0; } &:hover { color: blue; } > span { padding: 0 } }";
+        let scoped = parse_and_scope(css, "sc_n", false).unwrap();
+        assert_eq!(
+            scoped.scoped,
+            ".sc_n_card { color: red; }\n\
+             .sc_n_card .sc_n_title { margin: 0; }\n\
+             .sc_n_card:hover { color: blue; }\n\
+             .sc_n_card >span[data-scope=\"sc_n\"] { padding: 0 }\n"
+        );
+        assert_eq!(scoped.class_names, vec!["card", "title"]);
+    }
+
+    #[test]
+    fn test_nested_selector_lists_and_ampersand() {
+        let css = ".a, .b { .x, &.y { color: red; } [data-x=\"&\"] & { margin: 0; } }";
+        let scoped = parse_and_scope(css, "s", true).unwrap();
+        assert_eq!(
+            scoped.scoped,
+            ".s_a .s_x,.s_a.s_y,.s_b .s_x,.s_b.s_y {color:red;}\
+             [data-x=\"&\"] .s_a,[data-x=\"&\"] .s_b {margin:0;}"
+        );
+    }
+
+    #[test]
+    fn test_nested_media_flattened() {
+        let css = ".card { @media (min-width: 600px) { color: red; .title { margin: 0; } } }";
+        let scoped = parse_and_scope(css, "sc_n", false).unwrap();
+        assert_eq!(
+            scoped.scoped,
+            "@media (min-width: 600px) { .sc_n_card { color: red; } .sc_n_card .sc_n_title { margin: 0; } }\n"
+        );
+    }
+
+    #[test]
+    fn test_native_nesting() {
+        let css = ".card { color: red; .title { margin: 0; } &:hover { color: blue; } @media (print) { display: none; } }";
+        let options = ScopeOptions {
+            nesting: Nesting::Native,
+            ..ScopeOptions::default()
+        };
+        let scoped = parse_and_scope_with(css, "sc_n", &options).unwrap();
+        assert_eq!(
+            scoped.scoped,
+            ".sc_n_card { color: red; .sc_n_title { margin: 0; } &:hover { color: blue; } \
+             @media (print) { display: none; } }\n"
+        );
+    }
+
+    #[test]
+    fn test_options_from_flags() {
+        assert_eq!(
+            ScopeOptions::from_flags(false, false),
+            ScopeOptions::default()
+        );
+        let options = ScopeOptions::from_flags(true, true);
+        assert_eq!(options.nesting, Nesting::Native);
+        assert_eq!(options.custom_properties, CustomProperties::Declared);
+        assert!(!options.minify);
+    }
+
+    #[test]
+    fn test_flat_css_unchanged_by_nesting_mode() {
+        let css = ".a { color: red; }\n@media (print) { .b { margin: 0; } }";
+        let native = ScopeOptions {
+            nesting: Nesting::Native,
+            ..ScopeOptions::default()
+        };
+        assert_eq!(
+            parse_and_scope(css, "s", false).unwrap().scoped,
+            parse_and_scope_with(css, "s", &native).unwrap().scoped
+        );
+    }
+
+    #[test]
+    fn test_universal_selector() {
+        let css = "* { box-sizing: border-box; }\n\
+                   .card > *, .list *:hover { margin: 0; }\n\
+                   :not(*) { color: red; }\n\
+                   :global * { outline: none; }";
+        assert_eq!(
+            parse_and_scope(css, "sc_u", false).unwrap().scoped,
+            "[data-scope=\"sc_u\"] { box-sizing: border-box; }\n\
+             .sc_u_card >[data-scope=\"sc_u\"], .sc_u_list [data-scope=\"sc_u\"]:hover { margin: 0; }\n\
+             :not([data-scope=\"sc_u\"]) { color: red; }\n\
+             * { outline: none; }\n"
+        );
+    }
+
+    #[test]
+    fn test_root_and_host_selectors() {
+        let root = "[data-scope=\"sc_r\"]:not([data-scope=\"sc_r\"] *)";
+        let css = ":root { --gap: 4px; }\n\
+                   :host .title { margin: 0; }\n\
+                   :host(.active) { color: red; }\n\
+                   :global(:root) { color: black; }\n\
+                   .theme :root, p:host { color: blue; }";
+        assert_eq!(
+            parse_and_scope(css, "sc_r", false).unwrap().scoped,
+            format!(
+                "{0} {{ --gap: 4px; }}\n\
+                 {0} .sc_r_title {{ margin: 0; }}\n\
+                 {0}:is(.sc_r_active) {{ color: red; }}\n\
+                 :root {{ color: black; }}\n\
+                 .sc_r_theme :root, p[data-scope=\"sc_r\"]:host {{ color: blue; }}\n",
+                root
+            )
+        );
+    }
+
+    fn scope_properties(css: &str, custom_properties: CustomProperties) -> String {
+        let options = ScopeOptions {
+            custom_properties,
+            ..ScopeOptions::default()
+        };
+        parse_and_scope_with(css, "sc_v", &options).unwrap().scoped
+    }
+
+    #[test]
+    fn test_custom_properties_global_by_default() {
+        let css = ".root { --_accent: red; --accent: blue; color: var(--_accent); }\n";
+        assert_eq!(
+            parse_and_scope(css, "sc_v", false).unwrap().scoped,
+            ".sc_v_root { --_accent: red; --accent: blue; color: var(--_accent); }\n"
+        );
+    }
+
+    #[test]
+    fn test_private_custom_properties() {
+        let css = ".root { --_accent: red; --accent: blue; }\n\
+                   .item { color: var(--_accent, var(--brand)); border-color: var(--accent); }";
+        assert_eq!(
+            scope_properties(css, CustomProperties::Private),
+            ".sc_v_root { --sc_v_accent: red; --accent: blue; }\n\
+             .sc_v_item { color: var(--sc_v_accent, var(--brand)); border-color: var(--accent); }\n"
+        );
+    }
+
+    #[test]
+    fn test_declared_custom_properties() {
+        let css = ".root { --accent: red; --_gap: 4px; }\n\
+                   @media (min-width: 600px) { .root { --size: 2rem; } }\n\
+                   .item { color: var(--accent); margin: var(--_gap); font-size: var(--size); \
+                   background: var(--brand); content: \"--accent\"; }\n\
+                   .item--wide { padding: 0; }";
+        assert_eq!(
+            scope_properties(css, CustomProperties::Declared),
+            ".sc_v_root { --sc_v_accent: red; --sc_v_gap: 4px; }\n\
+             @media (min-width: 600px) { .sc_v_root { --sc_v_size: 2rem; } }\n\
+             .sc_v_item { color: var(--sc_v_accent); margin: var(--sc_v_gap); \
+             font-size: var(--sc_v_size); background: var(--brand); content: \"--accent\"; }\n\
+             .sc_v_item--wide { padding: 0; }\n"
+        );
+    }
+
+    #[test]
+    fn test_declared_custom_properties_skip_global_blocks() {
+        let css = ":global { :root { --brand: red; } }\n\
+                   @property --angle { syntax: '<angle>'; inherits: false; initial-value: 0deg; }\n\
+                   .a { color: var(--brand); rotate: var(--angle); }";
+        assert_eq!(
+            scope_properties(css, CustomProperties::Declared),
+            ":root { --brand: red; }\n\
+             @property --sc_v_angle { syntax: '<angle>'; inherits: false; initial-value: 0deg; }\n\
+             .sc_v_a { color: var(--brand); rotate: var(--sc_v_angle); }\n"
+        );
+    }
+}
@@ -0,0 +1,369 @@
+//! Input parsing for the component macros.
+
+use dioxus_style_core::CssSource;
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{Ident, ItemFn, LitBool, LitStr, Token};
+
+/// One stylesheet argument: `"file.css"` (a path or inline CSS, detected as in
+/// `scoped_style!`), `file = "..."` or `inline = "..."`.
+pub struct StyleArg {
+    key: Option<Ident>,
+    pub lit: LitStr,
+}
+
+impl StyleArg {
+    /// Parses the value of a `file` or `inline` key.
+    fn keyed(key: Ident, input: ParseStream) -> syn::Result<Self> {
+        input.parse::<Token![=]>()?;
+        Ok(Self {
+            key: Some(key),
+            lit: input.parse()?,
+        })
+    }
+
+    pub fn source(&self) -> CssSource {
+        match &self.key {
+            Some(key) if key == "file" => CssSource::File(self.lit.value()),
+            Some(_) => CssSource::Inline(self.lit.value()),
+            None => CssSource::detect(self.lit.value()),
+        }
+    }
+}
+
+impl ToTokens for StyleArg {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let lit = &self.lit;
+        tokens.extend(match &self.key {
+            Some(key) => quote!(#key = #lit),
+            None => quote!(#lit),
+        });
+    }
+}
+
+/// The stylesheets of `scoped_style!` and `#[with_css]`, merged into one scope:
+/// a comma-separated list of [`StyleArg`]s.
+///
+/// ```text
+/// #[with_css("base.css", "button.css")]
+/// #[with_css(file = "base.css", inline = ".button { color: red; }")]
+/// ```
+pub struct StyleSources {
+    pub args: Vec<StyleArg>,
+}
+
+impl StyleSources {
+    pub fn sources(&self) -> Vec<CssSource> {
+        self.args.iter().map(StyleArg::source).collect()
+    }
+}
+
+impl StyleSources {
+    /// Parses stylesheet arguments mixed with other `key = value` options.
+    ///
+    /// Keys other than `file` and `inline` are passed to `option`, which parses
+    /// the rest of the option and returns `false` for keys it doesn't know.
+    /// `expected` lists every accepted key for the error message.
+    fn parse_with(
+        input: ParseStream,
+        expected: &str,
+        mut option: impl FnMut(&Ident, ParseStream) -> syn::Result<bool>,
+    ) -> syn::Result<Self> {
+        let mut args = Vec::new();
+
+        while !input.is_empty() {
+            if input.peek(LitStr) {
+                args.push(StyleArg {
+                    key: None,
+                    lit: input.parse()?,
+                });
+            } else {
+                let key: Ident = input.parse()?;
+                if key == "file" || key == "inline" {
+                    args.push(StyleArg::keyed(key, input)?);
+                } else if !option(&key, input)? {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!("unknown option `{}`, expected {}", key, expected),
+                    ));
+                }
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        if args.is_empty() {
+            return Err(input.error("expected a CSS file path or inline CSS"));
+        }
+
+        Ok(Self { args })
+    }
+}
+
+impl Parse for StyleSources {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Self::parse_with(input, "`file` or `inline`", |_, _| Ok(false))
+    }
+}
+
+impl From<LitStr> for StyleSources {
+    fn from(lit: LitStr) -> Self {
+        Self {
+            args: vec![StyleArg { key: None, lit }],
+        }
+    }
+}
+
+impl ToTokens for StyleSources {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let args = &self.args;
+        tokens.extend(quote!(#(#args),*));
+    }
+}
+
+/// Arguments of `#[with_css]`: the stylesheets, plus `name = ident` to rename the
+/// `css` binding and `inject = false` to skip rendering the `<style>` tag.
+///
+/// ```text
+/// #[with_css("button.css", name = styles, inject = false)]
+/// ```
+pub struct WithCssArgs {
+    pub sources: StyleSources,
+    pub name: Option<Ident>,
+    pub inject: bool,
+}
+
+impl Parse for WithCssArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut name = None;
+        let mut inject = None;
+
+        let sources = StyleSources::parse_with(
+            input,
+            "`file`, `inline`, `name` or `inject`",
+            |key, input| {
+                if key == "name" {
+                    if name.is_some() {
+                        return Err(syn::Error::new(key.span(), "duplicate `name` option"));
+                    }
+                    input.parse::<Token![=]>()?;
+                    name = Some(input.parse::<Ident>()?);
+                } else if key == "inject" {
+                    if inject.is_some() {
+                        return Err(syn::Error::new(key.span(), "duplicate `inject` option"));
+                    }
+                    input.parse::<Token![=]>()?;
+                    inject = Some(input.parse::<LitBool>()?.value);
+                } else {
+                    return Ok(false);
+                }
+                Ok(true)
+            },
+        )?;
+
+        Ok(Self {
+            sources,
+            name,
+            inject: inject.unwrap_or(true),
+        })
+    }
+}
+
+/// Input of `component_with_css!`: `key: value` options followed by a function.
+///
+/// ```text
+/// component_with_css! {
+///     css: "button.css",
+///     /// Docs and attributes are kept
+///     pub fn Button<T: Display>(label: T) -> Element { ... }
+/// }
+/// ```
+pub struct ComponentWithCss {
+    pub css: LitStr,
+    pub func: ItemFn,
+}
+
+impl Parse for ComponentWithCss {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut css = None;
+
+        // Options end where the function's attributes, visibility or `fn` begin
+        while input.peek(Ident) && input.peek2(Token![:]) && !input.peek2(Token![::]) {
+            let key: Ident = input.parse()?;
+            input.parse::<Token![:]>()?;
+
+            match key.to_string().as_str() {
+                "css" => {
+                    if css.is_some() {
+                        return Err(syn::Error::new(key.span(), "duplicate `css` option"));
+                    }
+                    css = Some(input.parse::<LitStr>()?);
+                }
+                other => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!("unknown option `{}`, expected `css`", other),
+                    ));
+                }
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        let Some(css) = css else {
+            return Err(input.error(
+                "expected `css: \"file.css\"` before the function, e.g. \
+                 component_with_css! { css: \"file.css\", fn Component() -> Element { ... } }",
+            ));
+        };
+
+        let func = input.parse()?;
+        if !input.is_empty() {
+            return Err(input.error("unexpected tokens after the component function"));
+        }
+
+        Ok(Self { css, func })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(tokens: proc_macro2::TokenStream) -> syn::Result<ComponentWithCss> {
+        syn::parse2(tokens)
+    }
+
+    #[test]
+    fn test_parses_options_and_function() {
+        let input = parse(quote! {
+            css: "styles/button.css",
+            /// Renders a button with "fn " in its docs
+            #[allow(non_snake_case)]
+            pub(crate) fn Button<T: std::fmt::Display>(label: T) -> Element where T: Clone {
+                let s = "fn not_this()";
+                rsx! { button { "{label}" } }
+            }
+        })
+        .unwrap();
+
+        assert_eq!(input.css.value(), "styles/button.css");
+        assert_eq!(input.func.sig.ident, "Button");
+        assert_eq!(input.func.attrs.len(), 2);
+        assert_eq!(input.func.sig.generics.params.len(), 1);
+        assert!(input.func.sig.generics.where_clause.is_some());
+        assert!(matches!(input.func.vis, syn::Visibility::Restricted(_)));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let missing = parse(quote! { fn Button() -> Element { todo!() } });
+        assert!(missing
+            .err()
+            .unwrap()
+            .to_string()
+            .starts_with("expected `css:"));
+
+        let unknown = parse(quote! { style: "a.css", fn Button() -> Element { todo!() } });
+        assert_eq!(
+            unknown.err().unwrap().to_string(),
+            "unknown option `style`, expected `css`"
+        );
+
+        let duplicate = parse(quote! { css: "a.css", css: "b.css", fn B() -> Element { todo!() } });
+        assert_eq!(
+            duplicate.err().unwrap().to_string(),
+            "duplicate `css` option"
+        );
+
+        let not_a_string = parse(quote! { css: a, fn B() -> Element { todo!() } });
+        assert!(not_a_string.is_err());
+
+        let trailing = parse(quote! { css: "a.css", fn B() -> Element { todo!() } fn C() {} });
+        assert_eq!(
+            trailing.err().unwrap().to_string(),
+            "unexpected tokens after the component function"
+        );
+    }
+
+    #[test]
+    fn test_parses_style_sources() {
+        let sources: StyleSources = syn::parse2(quote! {
+            "base.css", file = "theme", inline = "a/b { }", ".x { }",
+        })
+        .unwrap();
+
+        assert_eq!(
+            sources.sources(),
+            vec![
+                CssSource::File("base.css".to_string()),
+                CssSource::File("theme".to_string()),
+                CssSource::Inline("a/b { }".to_string()),
+                CssSource::Inline(".x { }".to_string()),
+            ]
+        );
+        assert_eq!(
+            sources.to_token_stream().to_string(),
+            quote!("base.css", file = "theme", inline = "a/b { }", ".x { }").to_string()
+        );
+
+        let unknown = syn::parse2::<StyleSources>(quote! { name = styles, "a.css" });
+        assert_eq!(
+            unknown.err().unwrap().to_string(),
+            "unknown option `name`, expected `file` or `inline`"
+        );
+        let unknown = syn::parse2::<StyleSources>(quote! { path = "a.css" });
+        assert_eq!(
+            unknown.err().unwrap().to_string(),
+            "unknown option `path`, expected `file` or `inline`"
+        );
+        assert!(syn::parse2::<StyleSources>(quote! {}).is_err());
+    }
+
+    #[test]
+    fn test_parses_with_css_args() {
+        let args: WithCssArgs = syn::parse2(quote! { "a.css" }).unwrap();
+        assert!(args.name.is_none());
+        assert!(args.inject);
+
+        let args: WithCssArgs =
+            syn::parse2(quote! { name = styles, "a.css", inline = ".b { }", inject = false })
+                .unwrap();
+        assert_eq!(args.name.unwrap(), "styles");
+        assert!(!args.inject);
+        assert_eq!(args.sources.args.len(), 2);
+
+        let errors = [
+            (
+                quote! { "a.css", nam = styles },
+                "unknown option `nam`, expected `file`, `inline`, `name` or `inject`",
+            ),
+            (
+                quote! { "a.css", name = a, name = b },
+                "duplicate `name` option",
+            ),
+            (
+                quote! { "a.css", inject = "no" },
+                "expected boolean literal",
+            ),
+            (
+                quote! { name = styles },
+                "unexpected end of input, expected a CSS file path or inline CSS",
+            ),
+        ];
+        for (tokens, message) in errors {
+            assert_eq!(
+                syn::parse2::<WithCssArgs>(tokens)
+                    .err()
+                    .unwrap()
+                    .to_string(),
+                message
+            );
+        }
+    }
+}
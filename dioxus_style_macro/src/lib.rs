@@ -2,12 +2,14 @@
 //! Removed quick_component macro
 
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, ItemFn, LitStr};
+use quote::{format_ident, quote, ToTokens};
+use syn::{parse_macro_input, ItemFn};
 
-mod hash;
+use args::{ComponentWithCss, StyleSources, WithCssArgs};
+
+mod args;
 mod macros;
-mod style_parser;
+mod rsx_scope;
 
 // ============================================
 // CORE MACROS
@@ -25,57 +27,39 @@ pub fn css(input: TokenStream) -> TokenStream {
 
 #[proc_macro_attribute]
 pub fn with_css(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let css_file = parse_macro_input!(attr as LitStr);
+    let WithCssArgs {
+        sources,
+        name,
+        inject,
+    } = parse_macro_input!(attr as WithCssArgs);
     let func = parse_macro_input!(item as ItemFn);
 
-    let fn_name = &func.sig.ident;
-    let fn_inputs = &func.sig.inputs;
-    let fn_output = &func.sig.output;
-    let fn_vis = &func.vis;
-    let fn_body = &func.block;
-
-    // Validate return type
-    let has_element_return = match fn_output {
-        syn::ReturnType::Type(_, ty) => {
-            if let syn::Type::Path(type_path) = ty.as_ref() {
-                type_path
-                    .path
-                    .segments
-                    .last()
-                    .map(|seg| seg.ident == "Element")
-                    .unwrap_or(false)
-            } else {
-                false
-            }
-        }
-        _ => false,
-    };
-
-    if !has_element_return {
-        return syn::Error::new_spanned(
-            fn_output,
-            "#[with_css] can only be used on functions that return Element",
-        )
-        .to_compile_error()
-        .into();
+    if let Err(err) = check_element_return(&func.sig.output, "#[with_css]") {
+        return err.to_compile_error().into();
     }
 
+    let component_attr = component_attr(&func.attrs);
+    let fn_attrs = &func.attrs;
+    let fn_vis = &func.vis;
+    let fn_sig = &func.sig;
+    let binding = name.unwrap_or_else(|| format_ident!("css"));
+    let body = styled_body(
+        &func.block,
+        macros::stylesheet_scope(&sources),
+        &binding,
+        inject,
+    );
+
     let expanded = quote! {
-        #[::dioxus::prelude::component]
-        #fn_vis fn #fn_name(#fn_inputs) #fn_output {
+        #component_attr
+        #(#fn_attrs)*
+        #fn_vis #fn_sig {
             use ::dioxus::prelude::*;
 
             // Create scoped CSS variable
-            let css = ::dioxus_style::scoped_style!(#css_file);
-
-            // Auto-inject: Wrap user's rsx! to prepend <style> tag
-            let user_element = { #fn_body };
+            let #binding = ::dioxus_style::scoped_style!(#sources);
 
-            // Inject styles BEFORE rendering user content
-            rsx! {
-                style { dangerous_inner_html: "{::dioxus_style::inject_styles()}" }
-                {user_element}
-            }
+            #body
         }
     };
 
@@ -84,97 +68,145 @@ pub fn with_css(attr: TokenStream, item: TokenStream) -> TokenStream {
 
 #[proc_macro]
 pub fn component_with_css(input: TokenStream) -> TokenStream {
-    let input_str = input.to_string();
-
-    let css_start = match input_str.find("css:") {
-        Some(pos) => pos + 4,
-        None => {
-            return syn::Error::new(
-                proc_macro2::Span::call_site(),
-                "Expected format: component_with_css! { css: \"file.css\", fn Component() -> Element { ... } }"
-            )
-            .to_compile_error()
-            .into();
-        }
-    };
+    let ComponentWithCss { css, func } = parse_macro_input!(input as ComponentWithCss);
 
-    let after_css = input_str[css_start..].trim();
-    let quote_start = match after_css.find('"') {
-        Some(pos) => pos,
-        None => {
-            return syn::Error::new(
-                proc_macro2::Span::call_site(),
-                "Expected CSS file path in quotes",
-            )
-            .to_compile_error()
-            .into();
-        }
-    };
+    if let Err(err) = check_element_return(&func.sig.output, "component_with_css!") {
+        return err.to_compile_error().into();
+    }
 
-    let after_first_quote = &after_css[quote_start + 1..];
-    let quote_end = match after_first_quote.find('"') {
-        Some(pos) => pos,
-        None => {
-            return syn::Error::new(
-                proc_macro2::Span::call_site(),
-                "Unclosed CSS file path quote",
-            )
-            .to_compile_error()
-            .into();
-        }
-    };
+    let component_attr = component_attr(&func.attrs);
+    let fn_attrs = &func.attrs;
+    let fn_vis = &func.vis;
+    let fn_sig = &func.sig;
+    let css = StyleSources::from(css);
+    let body = styled_body(
+        &func.block,
+        macros::stylesheet_scope(&css),
+        &format_ident!("css"),
+        true,
+    );
 
-    let css_file = &after_first_quote[..quote_end];
+    let expanded = quote! {
+        #component_attr
+        #(#fn_attrs)*
+        #fn_vis #fn_sig {
+            use ::dioxus::prelude::*;
+            let css = ::dioxus_style::scoped_style!(#css);
 
-    let fn_start = match input_str.find("fn ") {
-        Some(pos) => pos,
-        None => {
-            return syn::Error::new(
-                proc_macro2::Span::call_site(),
-                "No function definition found",
-            )
-            .to_compile_error()
-            .into();
+            #body
         }
     };
 
-    let fn_code = &input_str[fn_start..];
-    let fn_tokens: TokenStream = match fn_code.parse() {
-        Ok(tokens) => tokens,
-        Err(e) => {
-            return syn::Error::new(
-                proc_macro2::Span::call_site(),
-                format!("Failed to parse function: {}", e),
-            )
-            .to_compile_error()
-            .into();
-        }
-    };
+    TokenStream::from(expanded)
+}
 
-    let func = parse_macro_input!(fn_tokens as ItemFn);
+/// Returns the `#[component]` attribute to add to a styled component, or nothing
+/// if the function already has one.
+fn component_attr(attrs: &[syn::Attribute]) -> Option<proc_macro2::TokenStream> {
+    let has_component = attrs.iter().any(|attr| {
+        attr.path()
+            .segments
+            .last()
+            .is_some_and(|seg| seg.ident == "component")
+    });
+
+    (!has_component).then(|| quote!(#[::dioxus::prelude::component]))
+}
 
-    let fn_name = &func.sig.ident;
-    let fn_inputs = &func.sig.inputs;
-    let fn_output = &func.sig.output;
-    let fn_vis = &func.vis;
-    let fn_body = &func.block;
+/// Checks that a styled component returns `Element`.
+fn check_element_return(output: &syn::ReturnType, macro_name: &str) -> syn::Result<()> {
+    let returns_element = match output {
+        syn::ReturnType::Type(_, ty) => match ty.as_ref() {
+            syn::Type::Path(type_path) => type_path
+                .path
+                .segments
+                .last()
+                .is_some_and(|seg| seg.ident == "Element"),
+            _ => false,
+        },
+        syn::ReturnType::Default => false,
+    };
 
-    let css_file_lit = LitStr::new(css_file, proc_macro2::Span::call_site());
+    if returns_element {
+        Ok(())
+    } else {
+        Err(syn::Error::new_spanned(
+            output,
+            format!(
+                "{} can only be used on functions that return Element",
+                macro_name
+            ),
+        ))
+    }
+}
 
-    let expanded = quote! {
-        #[::dioxus::prelude::component]
-        #fn_vis fn #fn_name(#fn_inputs) #fn_output {
-            use ::dioxus::prelude::*;
-            let css = ::dioxus_style::scoped_style!(#css_file_lit);
+/// Wraps a component body so it renders its own scope's `<style>` tag before the
+/// user's content. Only one instance per scope renders it (see `StyleInjection`).
+/// With `inject` false the body is left to a root that injects all styles.
+///
+/// Elements in the body's `rsx!` calls get a `data-scope` attribute for `scope`
+/// so element selectors match. `binding` is the local holding the scoped style.
+fn styled_body(
+    fn_body: &syn::Block,
+    scope: Option<String>,
+    binding: &syn::Ident,
+    inject: bool,
+) -> proc_macro2::TokenStream {
+    let fn_body = match scope {
+        Some(scope) => rsx_scope::scope_rsx(fn_body.to_token_stream(), &scope),
+        None => fn_body.to_token_stream(),
+    };
 
-            let user_element = { #fn_body };
+    if !inject {
+        return fn_body;
+    }
 
-            rsx! {
-                style { dangerous_inner_html: "{::dioxus_style::inject_styles()}" }
-                {user_element}
+    quote! {
+        // One handle per component instance, dropped on unmount. It re-renders
+        // this instance when it takes the <style> tag over from an unmounted one
+        let style_injection = use_hook(|| {
+            ::std::rc::Rc::new(
+                ::dioxus_style::StyleInjection::new(&#binding)
+                    .rerender_with(::dioxus::prelude::schedule_update()),
+            )
+        });
+
+        // Auto-inject: Wrap user's rsx! to prepend <style> tag. The body runs in a
+        // closure so an early `return` or `?` still comes back here, and the scope
+        // is only claimed once it rendered
+        let user_element = (|| -> Element #fn_body)()?;
+        let scoped_styles = style_injection.styles();
+
+        // Inject styles BEFORE rendering user content. The tag is always there,
+        // empty unless this instance owns the scope, so the server-rendered and
+        // hydrated trees have the same shape
+        rsx! {
+            style {
+                "data-dioxus-style": style_injection.scope().to_string(),
+                dangerous_inner_html: "{scoped_styles}"
             }
+            {user_element}
         }
-    };
+    }
+}
 
-    TokenStream::from(expanded)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_component_attr_not_duplicated() {
+        let plain: ItemFn = syn::parse_quote! {
+            /// Docs
+            #[allow(non_snake_case)]
+            fn Button() -> Element { todo!() }
+        };
+        assert!(component_attr(&plain.attrs).is_some());
+
+        let marked: ItemFn = syn::parse_quote! {
+            #[dioxus::prelude::component]
+            fn Button() -> Element { todo!() }
+        };
+        assert!(component_attr(&marked.attrs).is_none());
+    }
 }
@@ -1,116 +1,149 @@
 //! Procedural macro implementations for scoped styling.
 
+use std::collections::HashMap;
+use std::path::PathBuf;
+
 use proc_macro::TokenStream;
-use quote::quote;
+use proc_macro2::{Group, Span, TokenTree};
+use quote::{quote, ToTokens};
 use syn::{parse_macro_input, LitStr};
 
-use crate::hash::generate_hash;
-use crate::style_parser::parse_and_scope;
+use dioxus_style_core::{
+    check_declarations, generate_hash, load_sources, parse_and_scope_with, replace_interpolations,
+    wrap_declarations, CssError, CssSource, DynamicValue, ScopeOptions, ScopedCss,
+};
+
+use crate::args::StyleSources;
 
 /// Implementation of the `scoped_style!` macro.
 pub fn scoped_style_impl(input: TokenStream) -> TokenStream {
-    let input_str = parse_macro_input!(input as LitStr);
-    let css_content = input_str.value();
-
-    // Determine if this is a file path or inline CSS
-    if is_likely_file_path(&css_content) {
-        let file_path = css_content.clone();
-
-        // Try to find the CSS file in common locations
-        let possible_paths = [
-            file_path.clone(),              // As specified
-            format!("../{}", file_path),    // Parent directory
-            format!("../../{}", file_path), // Two levels up
-            format!("src/{}", file_path),   // In src
-        ];
-
-        let (actual_path, css_file_content) = match possible_paths.iter().find_map(|path| {
-            std::fs::read_to_string(path)
-                .ok()
-                .map(|content| (path.clone(), content))
-        }) {
-            Some(result) => result,
-            None => {
-                let error = format!(
-                    "Failed to find CSS file '{}'. Tried paths:\n{}",
-                    file_path,
-                    possible_paths
-                        .iter()
-                        .map(|p| format!("  - {}", p))
-                        .collect::<Vec<_>>()
-                        .join("\n")
-                );
-                return syn::Error::new(input_str.span(), error)
-                    .to_compile_error()
-                    .into();
-            }
-        };
+    let input = parse_macro_input!(input as StyleSources);
 
-        eprintln!("✅ Found CSS file at: {}", actual_path);
+    let loaded = match load_sources(&input.sources(), &manifest_dir()) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            return syn::Error::new(input.args[err.index()].lit.span(), err)
+                .to_compile_error()
+                .into();
+        }
+    };
 
-        // Determine if we should minify
-        let minify = cfg!(not(debug_assertions));
+    if loaded.files.is_empty() {
+        debug_log(format_args!("using inline CSS for scope {}", loaded.scope));
+    }
+    for file in &loaded.files {
+        debug_log(format_args!(
+            "found CSS file at {} for scope {}",
+            file.resolved.display(),
+            loaded.scope
+        ));
+    }
 
-        // We need to read the file at compile time to generate proper hash
-        // Generate hash with both file path and content
-        let scope_lit = generate_hash(&css_file_content, Some(&file_path));
-        let scoped = parse_and_scope(&css_file_content, &scope_lit, minify);
-        let scoped_css = scoped.scoped;
+    // Minify in release builds; nested rules are kept with `native-nesting`
+    let options = ScopeOptions {
+        minify: cfg!(not(debug_assertions)),
+        ..ScopeOptions::from_flags(
+            cfg!(feature = "native-nesting"),
+            cfg!(feature = "scoped-custom-properties"),
+        )
+    };
 
-        // Use the original file_path for include_str! (not actual_path)
-        // because include_str! is relative to the caller's file location
-        let include_path = &file_path;
+    let scope = loaded.scope;
 
-        // Generate code that uses include_str! at compile time
-        let expanded = quote! {
-            {
-                ::dioxus_style::lazy_static! {
-                    static ref STYLE_INSTANCE: ::dioxus_style::ScopedStyle = {
-                        // include_str! runs at compile time and tracks the file for rebuilds
-                        // Use the original path as specified by user
-                        let _css_tracker = include_str!(#include_path);
+    // Interpolated values become custom properties set through `css.style_attr()`
+    let dynamic = match replace_interpolations(&loaded.css, &scope) {
+        Ok(dynamic) => dynamic,
+        Err(err) => return css_error(&input.args[0].lit, "<merged>", &err),
+    };
+    let style = match dynamic_style(&dynamic.values, |expression| {
+        input
+            .args
+            .iter()
+            .map(|arg| &arg.lit)
+            .find(|lit| lit.value().contains(expression))
+            .unwrap_or(&input.args[0].lit)
+    }) {
+        Ok(style) => style,
+        Err(err) => return err.to_compile_error().into(),
+    };
 
-                        // Use pre-processed CSS
-                        let scope = #scope_lit.to_string();
-                        let css = #scoped_css.to_string();
+    let scoped = match parse_and_scope_with(&dynamic.css, &scope, &options) {
+        Ok(scoped) => scoped,
+        Err(err) => return css_error(&input.args[0].lit, "<merged>", &err),
+    };
+    let accessors = match class_accessors(&scope, &scoped, style.as_ref(), input.args[0].lit.span())
+    {
+        Ok(accessors) => accessors,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let scoped_css = embedded_css(scoped.scoped);
 
-                        eprintln!("🚀 [STATIC INIT] Loaded CSS from file: {}", #file_path);
-                        eprintln!("🎯 [STATIC INIT] Scope: {}, CSS length: {}", scope, css.len());
+    // include_str! resolves relative paths against the calling file, so pass
+    // the absolute paths that were actually read
+    let include_paths: Vec<String> = loaded
+        .files
+        .iter()
+        .map(|file| file.resolved.to_string_lossy().into_owned())
+        .collect();
+    // Sass partials only need tracking
+    let import_paths: Vec<String> = loaded
+        .files
+        .iter()
+        .flat_map(|file| &file.imports)
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
 
-                        ::dioxus_style::ScopedStyle::new(scope, css)
-                    };
+    let instance = match (input.args.len(), include_paths.as_slice()) {
+        (_, []) => quote! {
+            ::dioxus_style::ScopedStyle::new(scope, css)
+        },
+        (1, [include_path]) => quote! {
+            ::dioxus_style::ScopedStyle::from_file(scope, css, #include_path)
+        },
+        _ => {
+            let mut files = include_paths.iter();
+            let sources = input.sources().into_iter().map(|source| match source {
+                CssSource::File(_) => {
+                    let path = files.next();
+                    quote!(::dioxus_style::StyleSource::File(#path))
                 }
-
-                STYLE_INSTANCE.clone()
+                CssSource::Inline(css) => quote!(::dioxus_style::StyleSource::Inline(#css)),
+            });
+            quote! {
+                ::dioxus_style::ScopedStyle::from_sources(scope, css, &[#(#sources),*])
             }
-        };
+        }
+    };
 
-        TokenStream::from(expanded)
-    } else {
-        // Inline CSS - process at compile time as before
-        eprintln!("📝 Using inline CSS (not a file path)");
-
-        let scope = generate_hash(&css_content, None);
-        let minify = cfg!(not(debug_assertions));
-        let scoped = parse_and_scope(&css_content, &scope, minify);
-        let scoped_css = scoped.scoped;
-
-        let expanded = quote! {
-            {
-                ::dioxus_style::lazy_static! {
-                    static ref STYLE_INSTANCE: ::dioxus_style::ScopedStyle = {
-                        let scope = #scope.to_string();
-                        let css = #scoped_css.to_string();
-                        ::dioxus_style::ScopedStyle::new(scope, css)
-                    };
-                }
+    let expanded = quote! {
+        {
+            ::dioxus_style::lazy_static! {
+                static ref STYLE_INSTANCE: ::dioxus_style::ScopedStyle = {
+                    // include_str! runs at compile time and tracks the files for rebuilds
+                    #(let _css_tracker = include_str!(#include_paths);)*
+                    #(let _css_tracker = include_str!(#import_paths);)*
 
-                STYLE_INSTANCE.clone()
+                    // Use pre-processed CSS
+                    let scope = #scope.to_string();
+                    let css = #scoped_css.to_string();
+
+                    #instance
+                };
             }
-        };
 
-        TokenStream::from(expanded)
-    }
+            #accessors
+        }
+    };
+
+    TokenStream::from(expanded)
+}
+
+/// Returns the scope `scoped_style!` generates for `input`, or `None` if the
+/// stylesheets can't be loaded (`scoped_style!` reports that error itself).
+pub fn stylesheet_scope(input: &StyleSources) -> Option<String> {
+    load_sources(&input.sources(), &manifest_dir())
+        .ok()
+        .map(|loaded| loaded.scope)
 }
 
 /// Implementation of the `css!` macro for inline styles.
@@ -118,15 +151,24 @@ pub fn css_impl(input: TokenStream) -> TokenStream {
     let input_str = parse_macro_input!(input as LitStr);
     let css_content = input_str.value();
 
+    if let Err(err) = check_declarations(&css_content) {
+        return css_error(&input_str, "<inline>", &err);
+    }
+
     let scope = generate_hash(&css_content, None);
-    let wrapped_css = format!(".{} {{ {} }}", scope, css_content);
+    let dynamic = match replace_interpolations(&css_content, &scope) {
+        Ok(dynamic) => dynamic,
+        Err(err) => return css_error(&input_str, "<inline>", &err),
+    };
+    let style = match dynamic_style(&dynamic.values, |_| &input_str) {
+        Ok(style) => style,
+        Err(err) => return err.to_compile_error().into(),
+    };
 
     let minify = cfg!(not(debug_assertions));
-    let final_css = if minify {
-        crate::style_parser::parse_and_scope(&wrapped_css, &scope, true).scoped
-    } else {
-        wrapped_css
-    };
+    let final_css = embedded_css(wrap_declarations(&dynamic.css, &scope, minify));
+
+    let instance = instantiate(style.as_ref());
 
     let expanded = quote! {
         {
@@ -138,16 +180,266 @@ pub fn css_impl(input: TokenStream) -> TokenStream {
                 };
             }
 
-            STYLE_INSTANCE.clone()
+            #instance
         }
     };
 
     TokenStream::from(expanded)
 }
 
-/// Checks if a string looks like a file path.
-fn is_likely_file_path(s: &str) -> bool {
-    s.ends_with(".css") || s.contains('/') || s.contains('\\')
+/// Returns the CSS to compile into the binary: none when a build script has
+/// already extracted the styles into a stylesheet (`DIOXUS_STYLE_EXTRACTED`).
+fn embedded_css(css: String) -> String {
+    if std::env::var_os("DIOXUS_STYLE_EXTRACTED").is_some() {
+        String::new()
+    } else {
+        css
+    }
+}
+
+/// Environment variable that makes the macros print what they resolve, e.g.
+/// `DIOXUS_STYLE_DEBUG=1 cargo build`.
+const DEBUG_ENV: &str = "DIOXUS_STYLE_DEBUG";
+
+/// Prints a compile-time message if `DIOXUS_STYLE_DEBUG` is set.
+fn debug_log(message: std::fmt::Arguments<'_>) {
+    if std::env::var_os(DEBUG_ENV).is_some() {
+        eprintln!("[dioxus_style] {}", message);
+    }
+}
+
+/// Reports a CSS syntax error at the macro's string literal.
+fn css_error(input: &LitStr, source: &str, err: &CssError) -> TokenStream {
+    syn::Error::new(
+        input.span(),
+        format!("invalid CSS in {}", err.with_source(source)),
+    )
+    .to_compile_error()
+    .into()
+}
+
+/// Generates the expression building the `style` attribute value that sets each
+/// dynamic value, or `None` if there are none.
+///
+/// `literal` returns the macro argument an expression was written in, so errors
+/// point at it.
+fn dynamic_style<'a>(
+    values: &[DynamicValue],
+    literal: impl Fn(&str) -> &'a LitStr,
+) -> syn::Result<Option<proc_macro2::TokenStream>> {
+    if values.is_empty() {
+        return Ok(None);
+    }
+
+    let mut format = String::new();
+    let mut args = Vec::new();
+    for value in values {
+        if !format.is_empty() {
+            format.push(' ');
+        }
+        format.push_str(&format!("{}: {};", value.property, value.format));
+
+        for expression in &value.expressions {
+            let span = literal(expression).span();
+            let expr = syn::parse_str::<syn::Expr>(expression).map_err(|err| {
+                syn::Error::new(
+                    span,
+                    format!("invalid interpolation `{{{}}}`: {}", expression, err),
+                )
+            })?;
+            args.push(respan(expr.into_token_stream(), span));
+        }
+    }
+
+    Ok(Some(quote!(::std::format!(#format, #(#args),*))))
+}
+
+/// Gives every token `span`, so type errors in interpolated expressions point at
+/// the macro's string literal.
+fn respan(tokens: proc_macro2::TokenStream, span: Span) -> proc_macro2::TokenStream {
+    tokens
+        .into_iter()
+        .map(|token| match token {
+            TokenTree::Group(group) => {
+                let mut respanned = Group::new(group.delimiter(), respan(group.stream(), span));
+                respanned.set_span(span);
+                TokenTree::Group(respanned)
+            }
+            mut token => {
+                token.set_span(span);
+                token
+            }
+        })
+        .collect()
+}
+
+/// Generates a block-local wrapper around `STYLE_INSTANCE` with one accessor per
+/// class and ID found in the stylesheet, e.g. `css.button()` → `"sc_abc_button"`.
+///
+/// The wrapper derefs to `ScopedStyle` and displays as the scope, so `"{css}_button"`
+/// keeps working. A class and an ID with the same name share an accessor; other
+/// names mapping to the same accessor are an error reported at `span`. Names that
+/// aren't Rust identifiers even after conversion (e.g. `.²`) get no accessor.
+fn class_accessors(
+    scope: &str,
+    scoped: &ScopedCss,
+    style: Option<&proc_macro2::TokenStream>,
+    span: Span,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut seen: HashMap<String, String> = HashMap::with_capacity(scoped.class_names.len());
+    let mut methods = Vec::with_capacity(scoped.class_names.len() + scoped.id_names.len());
+
+    let classes = scoped.class_names.iter().map(|name| ('.', name));
+    let ids = scoped.id_names.iter().map(|name| ('#', name));
+    for (prefix, name) in classes.chain(ids) {
+        let method = accessor_name(name);
+        let raw = if RAW_KEYWORDS.contains(&method.as_str()) {
+            format!("r#{}", method)
+        } else {
+            method.clone()
+        };
+        let Ok(ident) = syn::parse_str::<syn::Ident>(&raw) else {
+            continue;
+        };
+
+        match seen.get(&method) {
+            // A class and an ID with the same name map to the same scoped string
+            Some(previous) if previous[1..] == **name => continue,
+            Some(previous) => {
+                return Err(syn::Error::new(
+                    span,
+                    format!(
+                        "`{}` and `{}{}` both map to the accessor `{}()`; rename one of them",
+                        previous, prefix, name, method
+                    ),
+                ));
+            }
+            None => {
+                seen.insert(method, format!("{}{}", prefix, name));
+            }
+        }
+
+        let value = format!("{}_{}", scope, name);
+        methods.push(quote! {
+            #[inline]
+            pub fn #ident(&self) -> &'static str {
+                #value
+            }
+        });
+    }
+
+    let instance = instantiate(style);
+
+    Ok(quote! {
+        #[derive(Debug, Clone)]
+        struct ScopedClasses(::dioxus_style::ScopedStyle);
+
+        #[allow(dead_code, non_snake_case)]
+        impl ScopedClasses {
+            /// Returns the underlying `ScopedStyle`.
+            #[inline]
+            pub fn into_inner(self) -> ::dioxus_style::ScopedStyle {
+                self.0
+            }
+
+            #(#methods)*
+        }
+
+        impl ::std::ops::Deref for ScopedClasses {
+            type Target = ::dioxus_style::ScopedStyle;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl ::std::convert::AsRef<::dioxus_style::ScopedStyle> for ScopedClasses {
+            fn as_ref(&self) -> &::dioxus_style::ScopedStyle {
+                &self.0
+            }
+        }
+
+        impl ::std::convert::From<ScopedClasses> for ::dioxus_style::ScopedStyle {
+            fn from(classes: ScopedClasses) -> Self {
+                classes.0
+            }
+        }
+
+        impl ::std::fmt::Display for ScopedClasses {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                ::std::fmt::Display::fmt(&self.0, f)
+            }
+        }
+
+        ScopedClasses(#instance)
+    })
+}
+
+/// Evaluates to a `ScopedStyle` handle for `STYLE_INSTANCE`, carrying the `style`
+/// attribute value built by `style` each time the macro runs, if any.
+fn instantiate(style: Option<&proc_macro2::TokenStream>) -> proc_macro2::TokenStream {
+    match style {
+        Some(style) => quote!(STYLE_INSTANCE.instantiate().with_style_attr(#style)),
+        None => quote!(STYLE_INSTANCE.instantiate()),
+    }
+}
+
+/// Converts a CSS class or ID name into a Rust method name.
+///
+/// Dashes become underscores, names starting with a digit get a `_` prefix, and
+/// names that would shadow the wrapper's own API or can't be raw identifiers get
+/// a `_` suffix.
+fn accessor_name(name: &str) -> String {
+    let mut method: String = name
+        .chars()
+        .map(|c| if c == '-' { '_' } else { c })
+        .collect();
+
+    if method.starts_with(|c: char| c.is_ascii_digit()) {
+        method.insert(0, '_');
+    }
+
+    if RESERVED_ACCESSORS.contains(&method.as_str()) {
+        method.push('_');
+    }
+
+    method
+}
+
+/// Names that can't be used as accessors as-is: non-raw keywords and the
+/// wrapper's own methods.
+const RESERVED_ACCESSORS: &[&str] = &[
+    "_",
+    "self",
+    "Self",
+    "super",
+    "crate",
+    "scope",
+    "style_attr",
+    "into_inner",
+    "clone",
+    "as_ref",
+    "deref",
+    "fmt",
+    "to_string",
+];
+
+/// Rust keywords that must be emitted as raw identifiers (`r#type`).
+const RAW_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while",
+    "abstract", "become", "box", "do", "final", "macro", "override", "priv", "try", "typeof",
+    "unsized", "virtual", "yield",
+];
+
+/// Returns the root of the crate being compiled, which CSS file paths are
+/// resolved against.
+fn manifest_dir() -> PathBuf {
+    std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default()
 }
 
 #[cfg(test)]
@@ -155,12 +447,35 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_is_likely_file_path() {
-        assert!(is_likely_file_path("button.css"));
-        assert!(is_likely_file_path("styles/button.css"));
-        assert!(is_likely_file_path("./button.css"));
+    fn test_accessor_name() {
+        assert_eq!(accessor_name("button"), "button");
+        assert_eq!(accessor_name("btn-primary"), "btn_primary");
+        assert_eq!(accessor_name("2col"), "_2col");
+        assert_eq!(accessor_name("self"), "self_");
+        assert_eq!(accessor_name("scope"), "scope_");
+        assert_eq!(accessor_name("style_attr"), "style_attr_");
+        assert_eq!(accessor_name("into-inner"), "into_inner_");
+        assert!(RAW_KEYWORDS.contains(&accessor_name("type").as_str()));
+    }
+
+    fn accessors(css: &str) -> syn::Result<String> {
+        let scoped = parse_and_scope_with(css, "sc", &ScopeOptions::default()).unwrap();
+        class_accessors("sc", &scoped, None, Span::call_site()).map(|tokens| tokens.to_string())
+    }
+
+    #[test]
+    fn test_class_accessors() {
+        let tokens = accessors(".type { } #main { } .main { } .² { }").unwrap();
+        assert!(tokens.contains("pub fn r#type"));
+        assert_eq!(tokens.matches("pub fn main").count(), 1);
+        assert!(!tokens.contains("²"));
 
-        assert!(!is_likely_file_path(".button { color: red; }"));
-        assert!(!is_likely_file_path("color: red; font-size: 16px;"));
+        let err = accessors(".btn-primary { } .btn_primary { }").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`.btn-primary` and `.btn_primary` both map to the accessor `btn_primary()`; \
+             rename one of them"
+        );
+        assert!(accessors(".scope { } #scope_ { }").is_err());
     }
 }
@@ -0,0 +1,342 @@
+//! Adds `data-scope` attributes to the elements of `rsx!` calls.
+//!
+//! Element selectors compile to `div[data-scope="sc_xxx"]`, so `#[with_css]` and
+//! `component_with_css!` rewrite the `rsx!` calls in the component body to put the
+//! attribute on every element. The rewrite works on tokens, following the node
+//! grammar of `rsx!` closely enough to tell elements from attributes, components
+//! and control flow.
+
+use proc_macro2::{Delimiter, Group, Spacing, TokenStream, TokenTree};
+use quote::quote;
+
+/// Rewrites every `rsx!` call in `tokens` so its elements carry `data-scope: scope`.
+pub fn scope_rsx(tokens: TokenStream, scope: &str) -> TokenStream {
+    let scope_attr = quote! { "data-scope": #scope, };
+    rewrite_macros(tokens, &scope_attr)
+}
+
+/// Finds `rsx!` calls in arbitrary Rust tokens and rewrites their bodies.
+fn rewrite_macros(tokens: TokenStream, scope_attr: &TokenStream) -> TokenStream {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut output = Vec::with_capacity(tokens.len());
+
+    for (i, token) in tokens.iter().enumerate() {
+        let TokenTree::Group(group) = token else {
+            output.push(token.clone());
+            continue;
+        };
+
+        let is_rsx = i >= 2
+            && matches!(&tokens[i - 1], TokenTree::Punct(p) if p.as_char() == '!')
+            && matches!(&tokens[i - 2], TokenTree::Ident(id) if id == "rsx");
+
+        let stream = if is_rsx {
+            rewrite_nodes(group.stream(), scope_attr)
+        } else {
+            rewrite_macros(group.stream(), scope_attr)
+        };
+        output.push(TokenTree::Group(with_stream(group, stream)));
+    }
+
+    output.into_iter().collect()
+}
+
+/// Rewrites a list of rsx nodes: the body of `rsx!`, an element or a component.
+fn rewrite_nodes(tokens: TokenStream, scope_attr: &TokenStream) -> TokenStream {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut output = TokenStream::new();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if is_attribute_start(&tokens, i) {
+            // Attributes, props and spreads run to the next comma
+            let end = tokens[i..]
+                .iter()
+                .position(|t| is_punct(t, ','))
+                .map_or(tokens.len(), |pos| i + pos + 1);
+            output.extend(rewrite_macros(
+                tokens[i..end].iter().cloned().collect(),
+                scope_attr,
+            ));
+            i = end;
+        } else if is_ident(&tokens[i], "for") || is_ident(&tokens[i], "if") {
+            i = rewrite_control_flow(&tokens, i, scope_attr, &mut output);
+        } else if is_ident(&tokens[i], "match") {
+            // Match arms are plain Rust; only `rsx!` calls inside them are rewritten
+            let end = (i + 1..tokens.len())
+                .find(|&j| is_body_group(&tokens, j))
+                .map_or(tokens.len(), |j| j + 1);
+            output.extend(rewrite_macros(
+                tokens[i..end].iter().cloned().collect(),
+                scope_attr,
+            ));
+            i = end;
+        } else if let Some(end) = element_body(&tokens, i) {
+            output.extend(tokens[i..end].iter().cloned());
+            let TokenTree::Group(body) = &tokens[end] else {
+                unreachable!("element_body returns the index of a brace group");
+            };
+
+            let mut stream = TokenStream::new();
+            if !has_scope_attribute(body) {
+                stream.extend(scope_attr.clone());
+            }
+            stream.extend(rewrite_nodes(body.stream(), scope_attr));
+            output.extend([TokenTree::Group(with_stream(body, stream))]);
+            i = end + 1;
+        } else if let TokenTree::Group(group) = &tokens[i] {
+            // Braced expressions and component bodies
+            let stream = if group.delimiter() == Delimiter::Brace && is_component_end(&tokens, i) {
+                rewrite_nodes(group.stream(), scope_attr)
+            } else {
+                rewrite_macros(group.stream(), scope_attr)
+            };
+            output.extend([TokenTree::Group(with_stream(group, stream))]);
+            i += 1;
+        } else {
+            output.extend([tokens[i].clone()]);
+            i += 1;
+        }
+    }
+
+    output
+}
+
+/// Rewrites an `if` chain or a `for` loop starting at `start` and returns the
+/// index after it.
+fn rewrite_control_flow(
+    tokens: &[TokenTree],
+    start: usize,
+    scope_attr: &TokenStream,
+    output: &mut TokenStream,
+) -> usize {
+    let mut i = start;
+
+    loop {
+        // The condition or iterator runs up to the body's braces
+        while i < tokens.len() && !is_body_group(tokens, i) {
+            output.extend(rewrite_macros(tokens[i].clone().into(), scope_attr));
+            i += 1;
+        }
+
+        let Some(TokenTree::Group(body)) = tokens.get(i) else {
+            return i;
+        };
+        let stream = rewrite_nodes(body.stream(), scope_attr);
+        output.extend([TokenTree::Group(with_stream(body, stream))]);
+        i += 1;
+
+        if !tokens.get(i).is_some_and(|t| is_ident(t, "else")) {
+            return i;
+        }
+        output.extend([tokens[i].clone()]);
+        i += 1;
+    }
+}
+
+/// Checks if the node at `i` is an attribute (`name: value`, `"name": value`),
+/// a shorthand attribute (`name,`) or a spread (`..attrs`).
+fn is_attribute_start(tokens: &[TokenTree], i: usize) -> bool {
+    let named = matches!(&tokens[i], TokenTree::Ident(_))
+        || matches!(&tokens[i], TokenTree::Literal(lit) if lit.to_string().starts_with('"'));
+
+    let colon = |j: usize| matches!(tokens.get(j), Some(TokenTree::Punct(p)) if p.as_char() == ':');
+    let single_colon = colon(i + 1) && !(is_joint(&tokens[i + 1]) && colon(i + 2));
+
+    let shorthand = matches!(&tokens[i], TokenTree::Ident(_))
+        && tokens.get(i + 1).map_or(true, |t| is_punct(t, ','));
+
+    let spread = is_punct(&tokens[i], '.') && tokens.get(i + 1).is_some_and(|t| is_punct(t, '.'));
+
+    (named && single_colon) || shorthand || spread
+}
+
+/// If an element (`div { ... }` or `my-element { ... }`) starts at `i`, returns
+/// the index of its body.
+fn element_body(tokens: &[TokenTree], i: usize) -> Option<usize> {
+    let TokenTree::Ident(name) = &tokens[i] else {
+        return None;
+    };
+    let name = name.to_string();
+
+    // Elements are single lowercase identifiers without underscores; anything
+    // else is a component
+    let mut end = i + 1;
+    if tokens.get(end).is_some_and(|t| is_punct(t, '-')) {
+        // Web components: ident(-ident)*
+        while tokens.get(end).is_some_and(|t| is_punct(t, '-'))
+            && matches!(tokens.get(end + 1), Some(TokenTree::Ident(_)))
+        {
+            end += 2;
+        }
+    } else if !name.starts_with(|c: char| c.is_ascii_lowercase())
+        || name.contains('_')
+        || (i > 0 && is_punct(&tokens[i - 1], ':'))
+    {
+        return None;
+    }
+
+    match tokens.get(end) {
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => Some(end),
+        _ => None,
+    }
+}
+
+/// Checks if the brace group at `i` is the body of a component (`Card { ... }`,
+/// `ui::Card { ... }`, `List::<T> { ... }`).
+fn is_component_end(tokens: &[TokenTree], i: usize) -> bool {
+    match i.checked_sub(1).map(|j| &tokens[j]) {
+        Some(TokenTree::Ident(_)) => true,
+        Some(TokenTree::Punct(p)) => p.as_char() == '>',
+        _ => false,
+    }
+}
+
+/// Checks if the token at `i` is the brace-delimited body of an `if` or `for`.
+fn is_body_group(tokens: &[TokenTree], i: usize) -> bool {
+    let TokenTree::Group(group) = &tokens[i] else {
+        return false;
+    };
+    // Closure bodies in the condition follow `|` or `move`
+    let after_closure =
+        i > 0 && (is_punct(&tokens[i - 1], '|') || is_ident(&tokens[i - 1], "move"));
+    group.delimiter() == Delimiter::Brace && !after_closure
+}
+
+/// Checks if an element body already sets `data-scope` itself.
+fn has_scope_attribute(body: &Group) -> bool {
+    let tokens: Vec<TokenTree> = body.stream().into_iter().collect();
+    tokens.iter().enumerate().any(|(i, token)| {
+        let named = match token {
+            TokenTree::Ident(id) => id == "data_scope",
+            TokenTree::Literal(lit) => lit.to_string() == "\"data-scope\"",
+            _ => false,
+        };
+        named && tokens.get(i + 1).is_some_and(|t| is_punct(t, ':'))
+    })
+}
+
+fn with_stream(group: &Group, stream: TokenStream) -> Group {
+    let mut rewritten = Group::new(group.delimiter(), stream);
+    rewritten.set_span(group.span());
+    rewritten
+}
+
+fn is_ident(token: &TokenTree, name: &str) -> bool {
+    matches!(token, TokenTree::Ident(id) if id == name)
+}
+
+fn is_punct(token: &TokenTree, ch: char) -> bool {
+    matches!(token, TokenTree::Punct(p) if p.as_char() == ch)
+}
+
+fn is_joint(token: &TokenTree) -> bool {
+    matches!(token, TokenTree::Punct(p) if p.spacing() == Spacing::Joint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scoped(tokens: TokenStream) -> String {
+        scope_rsx(tokens, "sc_test").to_string()
+    }
+
+    fn expected(tokens: TokenStream) -> String {
+        tokens.to_string()
+    }
+
+    #[test]
+    fn test_adds_scope_to_elements() {
+        let input = quote! {
+            rsx! {
+                div { class: "card", onclick: move |_| { count += 1 },
+                    h1 { "Title" }
+                    p { "{text}" }
+                }
+            }
+        };
+        let output = quote! {
+            rsx! {
+                div { "data-scope": "sc_test", class: "card", onclick: move |_| { count += 1 },
+                    h1 { "data-scope": "sc_test", "Title" }
+                    p { "data-scope": "sc_test", "{text}" }
+                }
+            }
+        };
+        assert_eq!(scoped(input), expected(output));
+    }
+
+    #[test]
+    fn test_components_get_no_scope_but_their_children_do() {
+        let input = quote! {
+            rsx! {
+                Card { title: "x", span {} }
+                ui::Button { label: "y" }
+                my_component {}
+                List::<u8> { items, li {} }
+            }
+        };
+        let output = quote! {
+            rsx! {
+                Card { title: "x", span { "data-scope": "sc_test", } }
+                ui::Button { label: "y" }
+                my_component {}
+                List::<u8> { items, li { "data-scope": "sc_test", } }
+            }
+        };
+        assert_eq!(scoped(input), expected(output));
+    }
+
+    #[test]
+    fn test_control_flow_and_nested_rsx() {
+        let input = quote! {
+            let header = rsx! { header {} };
+            rsx! {
+                if show { b {} } else if other { i {} } else { u {} }
+                for item in items.iter().filter(|x| { x.ok }) { li { key: "{item}" } }
+                {header}
+                {items.iter().map(|i| rsx! { span { "{i}" } })}
+                match tab { Tab::A => rsx! { a {} }, _ => None }
+            }
+        };
+        let output = quote! {
+            let header = rsx! { header { "data-scope": "sc_test", } };
+            rsx! {
+                if show { b { "data-scope": "sc_test", } } else if other { i { "data-scope": "sc_test", } } else { u { "data-scope": "sc_test", } }
+                for item in items.iter().filter(|x| { x.ok }) { li { "data-scope": "sc_test", key: "{item}" } }
+                {header}
+                {items.iter().map(|i| rsx! { span { "data-scope": "sc_test", "{i}" } })}
+                match tab { Tab::A => rsx! { a { "data-scope": "sc_test", } }, _ => None }
+            }
+        };
+        assert_eq!(scoped(input), expected(output));
+    }
+
+    #[test]
+    fn test_keeps_explicit_scope_and_attribute_values() {
+        let input = quote! {
+            rsx! {
+                div { "data-scope": "other", p {} }
+                section { data_scope: "x" }
+                my-element { "label": "a" }
+                input { r#type: "text", value: Foo { a: 1 }.a, ..attrs }
+            }
+        };
+        let output = quote! {
+            rsx! {
+                div { "data-scope": "other", p { "data-scope": "sc_test", } }
+                section { data_scope: "x" }
+                my-element { "data-scope": "sc_test", "label": "a" }
+                input { "data-scope": "sc_test", r#type: "text", value: Foo { a: 1 }.a, ..attrs }
+            }
+        };
+        assert_eq!(scoped(input), expected(output));
+    }
+
+    #[test]
+    fn test_other_macros_untouched() {
+        let input = quote! { let s = format!("{x}"); vec![div {}] };
+        assert_eq!(scoped(input.clone()), expected(input));
+    }
+}